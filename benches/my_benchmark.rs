@@ -1,16 +1,116 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::sync::Arc;
 
-fn fibonacci(n: u64) -> u64 {
-    match n {
-        0 => 1,
-        1 => 1,
-        n => fibonacci(n-1) + fibonacci(n-2),
+use chess::board::PerftPositions;
+use chess::bot::{self, SearchOptions};
+use chess::transposition::TranspositionTable;
+use chess::Board;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+const POSITIONS: [(&str, &str); 6] = [
+    ("position1", PerftPositions::POSITION1),
+    ("position2", PerftPositions::POSITION2),
+    ("position3", PerftPositions::POSITION3),
+    ("position4", PerftPositions::POSITION4),
+    ("position5", PerftPositions::POSITION5),
+    ("position6", PerftPositions::POSITION6),
+];
+
+fn board_from_fen(fen: &str) -> Board {
+    let mut board = Board::new();
+    board.load_fen(fen);
+    board
+}
+
+fn generate_moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate_moves");
+    for (name, fen) in POSITIONS {
+        let mut board = board_from_fen(fen);
+        group.bench_function(name, |b| b.iter(|| black_box(&mut board).generate_moves()));
+    }
+    group.finish();
+}
+
+fn make_undo_move(c: &mut Criterion) {
+    let mut group = c.benchmark_group("make_undo_move");
+    for (name, fen) in POSITIONS {
+        let mut board = board_from_fen(fen);
+        let moves = board.legal_moves();
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for _move in &moves {
+                    board.bench_make_move(black_box(_move));
+                    board.bench_undo_move();
+                }
+            })
+        });
     }
+    group.finish();
 }
 
-fn criterion_benchmark(c: &mut Criterion) {
-    c.bench_function("fib 20", |b| b.iter(|| fibonacci(black_box(20))));
+fn perft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("perft");
+    group.sample_size(10);
+    for depth in 1..=3 {
+        for (name, fen) in POSITIONS {
+            group.bench_with_input(BenchmarkId::new(name, depth), &depth, |b, &depth| {
+                b.iter_with_setup(
+                    || board_from_fen(fen),
+                    |board| board.benchmark(depth, depth),
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+fn search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search");
+    group.sample_size(10);
+    let depth = 3;
+    for (name, fen) in POSITIONS {
+        group.bench_with_input(BenchmarkId::new(name, depth), &depth, |b, &depth| {
+            b.iter_with_setup(
+                || board_from_fen(fen),
+                |mut board| bot::search(&mut board, depth),
+            )
+        });
+    }
+    group.finish();
+}
+
+/// time to depth of Lazy SMP for each number of threads, all sharing one cleared table
+fn parallel_search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parallel_search");
+    group.sample_size(10);
+    let table = Arc::new(TranspositionTable::new(bot::DEFAULT_HASH));
+    for threads in [1, 2, 4, 8] {
+        for (name, fen) in POSITIONS {
+            group.bench_with_input(BenchmarkId::new(name, threads), &threads, |b, &threads| {
+                let options = SearchOptions {
+                    threads,
+                    table: Some(table.clone()),
+                    ..SearchOptions::new(4)
+                };
+                b.iter_batched(
+                    || {
+                        table.clear();
+                        board_from_fen(fen)
+                    },
+                    |mut board| bot::search_observed(&mut board, &options, &mut |_: &_| {}),
+                    BatchSize::SmallInput,
+                )
+            });
+        }
+    }
+    group.finish();
 }
 
-criterion_group!(benches, criterion_benchmark);
-criterion_main!(benches);
\ No newline at end of file
+criterion_group!(
+    benches,
+    generate_moves,
+    make_undo_move,
+    perft,
+    search,
+    parallel_search
+);
+criterion_main!(benches);
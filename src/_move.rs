@@ -14,7 +14,7 @@ pub(crate) mod MoveFlags {
     pub(crate) const EN_PASSANT: u8 = 1 << 6;
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Move {
     pub start: u8,
     pub end: u8,
@@ -33,12 +33,12 @@ impl Move {
         en_passant: bool,
     ) -> Move {
         Move {
-            start: start,
-            end: end,
+            start,
+            end,
             flags: castling
-                & (promotion << 2)
-                & ((two_square_advance as u8) << 5)
-                & ((en_passant as u8) << 6),
+                | (promotion << 2)
+                | ((two_square_advance as u8) << 5)
+                | ((en_passant as u8) << 6),
         }
     }
 
@@ -65,11 +65,11 @@ impl Move {
     }
 
     pub fn castling(&self) -> u8 {
-        (self.flags & 0x3) as u8
+        self.flags & 0x3
     }
 
     pub fn promotion(&self) -> u8 {
-        ((self.flags & 0x1c) >> 2) as u8
+        (self.flags & 0x1c) >> 2
     }
 
     pub fn two_square_advance(&self) -> bool {
@@ -83,6 +83,18 @@ impl Move {
 
 impl fmt::Display for Move {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{{{},{}}}", Position::from_index(self.start).to_string(), Position::from_index(self.end).to_string())
+        write!(f, "{{{},{}}}", Position::from_index(self.start), Position::from_index(self.end))
     }
 }
+
+/// error returned when trying to play a move that is not legal in the current position
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IllegalMove(pub Move);
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "illegal move {}", self.0)
+    }
+}
+
+impl std::error::Error for IllegalMove {}
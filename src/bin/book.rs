@@ -0,0 +1,62 @@
+use chess::pgn;
+use chess::polyglot::BookBuilder;
+
+const USAGE: &str = "usage: book --output <book.bin> [options] <pgn files>
+
+options:
+    --ply <n>                  moves of each game added to the book, default 20
+    --min-count <n>            leave out moves played less often, default 1
+    --min-score <x>            leave out moves scoring less for the side playing them, ex.: 0.4";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    std::process::exit(1)
+}
+
+fn main() -> std::io::Result<()> {
+    let mut builder = BookBuilder::new(20);
+    let mut output = None;
+    let mut files = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| fail(&format!("missing value for {}", arg)))
+        };
+        match arg.as_str() {
+            "--output" | "-o" => output = Some(value()),
+            "--ply" => builder.max_ply = value().parse().unwrap_or_else(|_| fail("invalid ply")),
+            "--min-count" => {
+                builder.min_count = value().parse().unwrap_or_else(|_| fail("invalid count"))
+            }
+            "--min-score" => {
+                builder.min_score = value().parse().unwrap_or_else(|_| fail("invalid score"))
+            }
+            _ if arg.starts_with('-') => fail(&format!("unknown argument {}", arg)),
+            _ => files.push(arg),
+        }
+    }
+    let output = output.unwrap_or_else(|| fail("missing --output"));
+    if files.is_empty() {
+        fail("missing pgn files");
+    }
+
+    let mut games = 0;
+    for file in files {
+        for (i, game) in pgn::parse_games(&std::fs::read_to_string(&file)?)
+            .iter()
+            .enumerate()
+        {
+            match builder.add_pgn_game(game) {
+                Ok(()) => games += 1,
+                Err(err) => eprintln!("{}: skipped game {}: {}", file, i + 1, err),
+            }
+        }
+    }
+
+    let book = builder.build();
+    std::fs::write(&output, book.to_bytes())?;
+    println!("{} games, {} entries written to {}", games, book.len(), output);
+    Ok(())
+}
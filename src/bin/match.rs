@@ -0,0 +1,141 @@
+use chess::engine_match::{self, EngineConfig, MatchConfig, Sprt, SprtResult};
+
+const USAGE: &str = "usage: match --engine1 <path|depth=N> --engine2 <path|depth=N> [options]
+
+options:
+    --option1 <name=value>     UCI option for the first engine, may be repeated
+    --option2 <name=value>     UCI option for the second engine, may be repeated
+    --limits1 <limits>         search limits of the first engine, ex.: \"depth 6\"
+    --limits2 <limits>         search limits of the second engine
+    --games <n>                number of games, default 2
+    --openings <file>          FEN or EPD file with starting positions
+    --tc <time control>        time control in seconds, ex.: 10+0.1 or 40/60:30
+    --pgn <file>               write all games to this file
+    --sprt <elo0,elo1[,alpha,beta]>
+    --resign <cp,moves>        resign adjudication
+    --draw <cp,moves,ply>      draw adjudication
+    --max-ply <n>              draw games after this many plies";
+
+fn engine(spec: &str) -> EngineConfig {
+    match spec.strip_prefix("depth=") {
+        Some(depth) => EngineConfig::Builtin {
+            depth: depth.parse().unwrap_or_else(|_| fail("invalid depth")),
+        },
+        None => EngineConfig::Uci {
+            path: spec.to_string(),
+            options: Vec::new(),
+            limits: None,
+        },
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    std::process::exit(1)
+}
+
+fn numbers(value: &str) -> Vec<f64> {
+    value
+        .split(',')
+        .map(|n| n.trim().parse().unwrap_or_else(|_| fail("invalid number")))
+        .collect()
+}
+
+fn main() -> std::io::Result<()> {
+    let mut engines = [None, None];
+    let mut options: [Vec<(String, String)>; 2] = [Vec::new(), Vec::new()];
+    let mut limits = [None, None];
+    let mut config = MatchConfig::new(
+        EngineConfig::Builtin { depth: 1 },
+        EngineConfig::Builtin { depth: 1 },
+        2,
+    );
+    let mut pgn_file = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| fail(&format!("missing value for {}", arg)));
+        let index = arg.ends_with('2') as usize;
+        match arg.as_str() {
+            "--engine1" | "--engine2" => engines[index] = Some(engine(&value)),
+            "--option1" | "--option2" => match value.split_once('=') {
+                Some((name, value)) => options[index].push((name.to_string(), value.to_string())),
+                None => fail("options are given as name=value"),
+            },
+            "--limits1" | "--limits2" => limits[index] = Some(value),
+            "--games" => config.games = value.parse().unwrap_or_else(|_| fail("invalid games")),
+            "--openings" => {
+                config.openings = engine_match::parse_openings(&std::fs::read_to_string(value)?)
+            }
+            "--tc" => config.clock = value.parse().unwrap_or_else(|err: String| fail(&err)),
+            "--pgn" => pgn_file = Some(value),
+            "--sprt" => {
+                let n = numbers(&value);
+                let mut sprt = Sprt::new(n[0], *n.get(1).unwrap_or_else(|| fail("missing elo1")));
+                if n.len() == 4 {
+                    sprt.alpha = n[2];
+                    sprt.beta = n[3];
+                }
+                config.sprt = Some(sprt);
+            }
+            "--resign" => {
+                let n = numbers(&value);
+                config.adjudication.resign_score = Some(n[0] as i32);
+                config.adjudication.resign_moves = *n.get(1).unwrap_or(&3.0) as usize;
+            }
+            "--draw" => {
+                let n = numbers(&value);
+                config.adjudication.draw_score = Some(n[0] as i32);
+                config.adjudication.draw_moves = *n.get(1).unwrap_or(&8.0) as usize;
+                config.adjudication.draw_after_ply = *n.get(2).unwrap_or(&80.0) as usize;
+            }
+            "--max-ply" => {
+                config.adjudication.max_ply =
+                    Some(value.parse().unwrap_or_else(|_| fail("invalid max ply")))
+            }
+            _ => fail(&format!("unknown argument {}", arg)),
+        }
+    }
+
+    for i in 0..2 {
+        let mut engine = engines[i]
+            .take()
+            .unwrap_or_else(|| fail(&format!("missing --engine{}", i + 1)));
+        if let EngineConfig::Uci {
+            options: engine_options,
+            limits: engine_limits,
+            ..
+        } = &mut engine
+        {
+            *engine_options = std::mem::take(&mut options[i]);
+            *engine_limits = limits[i].take();
+        }
+        config.engines[i] = engine;
+    }
+
+    let result = engine_match::run(&config, |result| {
+        println!("{}", result);
+    })?;
+
+    if let Some(sprt) = &config.sprt {
+        let (lower, upper) = sprt.bounds();
+        println!(
+            "SPRT: llr {:.2} ({:.2}, {:.2}) {}",
+            sprt.llr(result.wins, result.draws, result.losses),
+            lower,
+            upper,
+            match result.sprt {
+                Some(SprtResult::AcceptH0) => "H0 accepted",
+                Some(SprtResult::AcceptH1) => "H1 accepted",
+                _ => "no decision",
+            }
+        );
+    }
+
+    if let Some(pgn_file) = pgn_file {
+        std::fs::write(pgn_file, result.pgn.join("\n"))?;
+    }
+    Ok(())
+}
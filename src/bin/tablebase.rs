@@ -0,0 +1,41 @@
+use std::time::Instant;
+
+use chess::tablebase::Tablebases;
+
+const USAGE: &str = "usage: tablebase --output <dir> [materials]
+
+generates the tables of the materials, ex.: KQvK KRvKP, and the tables they depend on, all
+tables with three pieces by default";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    std::process::exit(1)
+}
+
+fn main() -> std::io::Result<()> {
+    let mut output = None;
+    let mut materials = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" | "-o" => output = args.next(),
+            _ if arg.starts_with('-') => fail(&format!("unknown argument {}", arg)),
+            _ => materials.push(arg),
+        }
+    }
+    let output = output.unwrap_or_else(|| fail("missing --output"));
+    if materials.is_empty() {
+        materials = vec!["KQvK".into(), "KRvK".into(), "KPvK".into()];
+    }
+
+    let mut tablebases = Tablebases::new();
+    for material in materials {
+        let start = Instant::now();
+        if let Err(err) = tablebases.generate(&material) {
+            fail(&err);
+        }
+        println!("{} generated in {:.1?}", material, start.elapsed());
+    }
+    tablebases.save(&output)
+}
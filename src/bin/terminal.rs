@@ -0,0 +1,57 @@
+use chess::{Book, Clock, EnginePlayer, Game, Player, TerminalPlayer};
+
+const USAGE: &str = "usage: terminal [--black] [--depth <n>] [--tc <time control>] [--book <polyglot book>]";
+
+fn main() {
+    let mut human_white = true;
+    let mut depth = 5;
+    let mut clock = Clock::unlimited();
+    let mut book = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--black" => human_white = false,
+            "--depth" => match args.next().and_then(|d| d.parse().ok()) {
+                Some(d) => depth = d,
+                None => return eprintln!("{}", USAGE),
+            },
+            "--tc" => match args.next().map(|tc| tc.parse()) {
+                Some(Ok(c)) => clock = c,
+                Some(Err(err)) => return eprintln!("{}", err),
+                None => return eprintln!("{}", USAGE),
+            },
+            "--book" => match args.next().map(Book::open) {
+                Some(Ok(b)) => book = Some(b),
+                Some(Err(err)) => return eprintln!("unable to read book: {}", err),
+                None => return eprintln!("{}", USAGE),
+            },
+            _ => return eprintln!("{}", USAGE),
+        }
+    }
+
+    let human: Box<dyn Player> = Box::new(TerminalPlayer);
+    let mut engine = EnginePlayer::new(depth);
+    if let Some(book) = book {
+        engine = engine.with_book(book);
+    }
+    let engine: Box<dyn Player> = Box::new(engine);
+    let mut game = if human_white {
+        Game::new(human, engine)
+    } else {
+        Game::new(engine, human)
+    };
+    game.set_clock(clock);
+
+    println!("{}", game);
+    while !game.is_over() {
+        match game.step() {
+            Ok(Some(_)) => {
+                let entry = game.board().record().entries().last().unwrap();
+                println!("\n{}\n{}", entry.san, game);
+            }
+            Ok(None) => println!("\n{}", game),
+            Err(err) => println!("{}", err),
+        }
+    }
+}
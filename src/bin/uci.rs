@@ -0,0 +1,6 @@
+use chess::uci::UciEngine;
+
+fn main() -> std::io::Result<()> {
+    let stdin = std::io::stdin();
+    UciEngine::new().run(stdin.lock(), std::io::stdout())
+}
@@ -1,14 +1,16 @@
 use termion::color;
 
-use crate::_move::{Move, MoveFlags};
+use crate::_move::{IllegalMove, Move, MoveFlags};
 use crate::change::Change;
+use crate::game_record::{GameRecord, RecordEntry};
+use crate::game_result::{GameResult, Reason};
 use crate::move_generator::MoveGenerator;
 use crate::piece::{Piece, PieceColor, PieceType};
 use crate::piece_list::PieceList;
 use crate::piece_moves::PieceMoves;
-use crate::player::PlayerType;
 use crate::position::Position;
 use crate::state::State;
+use crate::zobrist::ZOBRIST;
 use array_init::array_init;
 use core::num;
 use std::fmt;
@@ -24,6 +26,7 @@ pub mod PerftPositions {
         "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
 }
 
+#[derive(Clone)]
 pub(crate) struct Data {
     pub(crate) board: [Piece; 8 * 8],
     pub(crate) piece_moves: PieceMoves,
@@ -31,20 +34,24 @@ pub(crate) struct Data {
     pub(crate) piece_list: PieceList,
     pub(crate) not_able_to_castle: u8, // bit 0 white queenside, bit 1 white kingside, bit 2 black queenside, bit 3 black kingside
     pub(crate) two_square_advance: u8, // intermittend position of the pawn while advancing two squares starting at 1
+    pub(crate) castling_rooks: [u8; 4], // start position of the castling rooks, same bit order as not_able_to_castle
+    pub(crate) chess960: bool,
+    pub(crate) hash: u64,
 }
 
+#[derive(Clone)]
 pub struct Board {
     pub(crate) move_generator: MoveGenerator,
     pub(crate) data: Data,
     pub state: State,
-    players: [PlayerType; 2],
     ply: usize,
     fifty_move_counter: u8,
     changes: Vec<Change>,
+    record: GameRecord,
 }
 
 impl Board {
-    pub fn new(player1: PlayerType, player2: PlayerType) -> Board {
+    pub fn new() -> Board {
         let mut board = Board {
             data: Data {
                 board: array_init(|_| Piece::empty()),
@@ -53,56 +60,201 @@ impl Board {
                 piece_list: PieceList::new(),
                 not_able_to_castle: 0x0f,
                 two_square_advance: 0,
+                castling_rooks: [0, 7, 56, 63],
+                chess960: false,
+                hash: 0,
             },
             state: State::Playing,
             move_generator: MoveGenerator::new(),
-            players: [player1, player2],
             ply: 0,
             fifty_move_counter: 0,
             changes: Vec::new(),
+            record: GameRecord::new(String::new(), 0),
         };
         board.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
         board
     }
 
+    pub fn from_fen(fen: &str) -> Board {
+        let mut board = Board::new();
+        board.load_fen(fen);
+        board
+    }
+
     pub fn get_piece_at_position(&self, pos: Position) -> Piece {
         self.data.board[pos.to_index() as usize].clone()
     }
 
     pub fn piece_able_to_move(&self, pos: Position) -> bool {
-        self.move_generator.moves.iter().any(|m| {
-            m.start == pos.to_index()
-                && self
-                    .get_piece_at_position(pos)
-                    .is_white()
-                    == self.data.white_turn
-        })
+        !self.moves_from(pos).is_empty()
+    }
+
+    pub fn piece_able_to_move_to_pos(&self, old_pos: Position, new_pos: Position) -> bool {
+        self.moves_from(old_pos)
+            .iter()
+            .any(|m| m.end == new_pos.to_index())
+            || old_pos == new_pos
+    }
+
+    /// all legal moves in the current position
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut move_generator = MoveGenerator::new();
+        move_generator.generate_moves(&self.data);
+        move_generator.moves
     }
 
-    pub fn piece_able_to_move_to_pos(
+    pub fn is_legal(&self, _move: &Move) -> bool {
+        self.legal_moves().contains(_move)
+    }
+
+    /// all legal moves of the piece at `pos`
+    pub fn moves_from(&self, pos: Position) -> Vec<Move> {
+        let mut moves = self.legal_moves();
+        moves.retain(|m| m.start == pos.to_index());
+        moves
+    }
+
+    /// the legal move from `start` to `end`, promoting to `promotion` when a pawn reaches the
+    /// last rank
+    pub fn find_move(
         &self,
-        old_pos: Position,
-        new_pos: Position
-    ) -> bool {
-        self.move_generator.moves.iter().any(|m| {
-            (m.start == old_pos.to_index())
-                && (m.end == new_pos.to_index())
-                && self
-                    .get_piece_at_position(old_pos)
-                    .is_white()
-                    == self.data.white_turn
-        }) || old_pos == new_pos
+        start: Position,
+        end: Position,
+        promotion: Option<PieceType>,
+    ) -> Option<Move> {
+        let promotion = promotion.map_or(0, |piece| piece as u8 - 1);
+        self.moves_from(start)
+            .into_iter()
+            .find(|m| m.end == end.to_index() && m.promotion() == promotion)
+    }
+
+    /// whether moving from `start` to `end` promotes a pawn, the piece then has to be chosen
+    pub fn is_promotion(&self, start: Position, end: Position) -> bool {
+        self.moves_from(start)
+            .iter()
+            .any(|m| m.end == end.to_index() && m.promotion() != 0)
+    }
+
+    /// plies since the last capture or pawn move
+    pub fn fifty_move_counter(&self) -> u8 {
+        self.fifty_move_counter
     }
 
+    /// whether the side to move is in check
+    pub fn is_check(&self) -> bool {
+        let mut move_generator = MoveGenerator::new();
+        move_generator.generate_moves(&self.data);
+        move_generator.in_check
+    }
+
+    /// the square of the king of the side to move if it is in check
+    pub fn checked_king(&self) -> Option<Position> {
+        if !self.is_check() {
+            return None;
+        }
+        (0..64).map(Position::from_index).find(|pos| {
+            let piece = self.get_piece_at_position(*pos);
+            piece.is_king() && piece.is_white() == self.white_turn()
+        })
+    }
+
+    /// the squares the last move of the record went from and to
+    pub fn last_move(&self) -> Option<(Position, Position)> {
+        let _move = &self.record.entries().last()?._move;
+        Some((Position::from_index(_move.start), Position::from_index(_move.end)))
+    }
+
+    /// whether `_move` puts the opponent in check, `_move` has to be legal
+    pub fn gives_check(&self, _move: &Move) -> bool {
+        let mut board = self.scratch();
+        board.make_move(_move);
+        board.is_check()
+    }
+
+    /// positions of all pieces of `color` attacking `pos`
+    pub fn attackers_of(&self, pos: Position, color: PieceColor) -> Vec<Position> {
+        let attackers = MoveGenerator::attackers_of(&self.data, pos.to_index(), color as i8);
+        (0..64)
+            .filter(|i| attackers & (1 << i) != 0)
+            .map(Position::from_index)
+            .collect()
+    }
+
+    /// the position of `fen` if it is a valid and legal position, `load_fen` expects one
+    pub fn try_from_fen(fen: &str) -> Result<Board, String> {
+        let sections: Vec<&str> = fen.split_whitespace().collect();
+        if sections.len() < 2 {
+            return Err(String::from("missing the side to move"));
+        }
+        let ranks: Vec<&str> = sections[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(String::from("the board needs 8 ranks"));
+        }
+        for (i, rank) in ranks.iter().enumerate() {
+            let mut squares = 0;
+            for c in rank.chars() {
+                match c {
+                    '1'..='8' => squares += c.to_digit(10).unwrap(),
+                    'p' | 'P' if i == 0 || i == 7 => {
+                        return Err(String::from("pawns on the first or last rank"))
+                    }
+                    'p' | 'n' | 'b' | 'r' | 'q' | 'k' | 'P' | 'N' | 'B' | 'R' | 'Q' | 'K' => {
+                        squares += 1
+                    }
+                    _ => return Err(format!("invalid piece {}", c)),
+                }
+            }
+            if squares != 8 {
+                return Err(format!("rank {} does not have 8 squares", 8 - i));
+            }
+        }
+        for king in ['K', 'k'] {
+            if sections[0].matches(king).count() != 1 {
+                return Err(String::from("each side needs exactly one king"));
+            }
+        }
+        if sections[1] != "w" && sections[1] != "b" {
+            return Err(format!("invalid side to move {}", sections[1]));
+        }
+        let castling = sections.get(2).copied().unwrap_or("-");
+        let valid_castling = |c: char| matches!(c.to_ascii_lowercase(), 'a'..='h' | 'k' | 'q');
+        if castling != "-" && !castling.chars().all(valid_castling) {
+            return Err(format!("invalid castling rights {}", castling));
+        }
+        let en_passant = sections.get(3).copied().unwrap_or("-");
+        let valid_en_passant = matches!(en_passant.as_bytes(), [b'a'..=b'h', b'3' | b'6']);
+        if en_passant != "-" && !valid_en_passant {
+            return Err(format!("invalid en passant square {}", en_passant));
+        }
+
+        let board = Board::from_fen(fen);
+        let color = if board.white_turn() {
+            PieceColor::White
+        } else {
+            PieceColor::Black
+        };
+        let king = (0..64).map(Position::from_index).find(|pos| {
+            let piece = board.get_piece_at_position(*pos);
+            piece.is_king() && piece.is_white() != board.white_turn()
+        });
+        if king.is_some_and(|king| !board.attackers_of(king, color).is_empty()) {
+            return Err(String::from("the side not to move is in check"));
+        }
+        Ok(board)
+    }
+
+    #[allow(clippy::chars_next_cmp, clippy::collapsible_if)]
     pub fn load_fen(&mut self, fen: &str) {
         self.state = State::Playing;
         self.data.not_able_to_castle = 0x0f;
+        self.data.two_square_advance = 0;
+        self.fifty_move_counter = 0;
 
         for piece in &mut self.data.board {
             piece.set_empty()
         }
 
-        let sections: Vec<&str> = fen.split(' ').collect();
+        let sections: Vec<&str> = fen.split_whitespace().collect();
         let mut file = 7;
         let mut rank = 0;
         let mut white_uuid = 0;
@@ -135,33 +287,14 @@ impl Board {
             "KQkq"
         };
 
-        self.data.not_able_to_castle ^= if castling_rights.contains('Q') {
-            1 << 0
-        } else {
-            0
-        };
-        self.data.not_able_to_castle ^= if castling_rights.contains('K') {
-            1 << 1
-        } else {
-            0
-        };
-        self.data.not_able_to_castle ^= if castling_rights.contains('q') {
-            1 << 2
-        } else {
-            0
-        };
-        self.data.not_able_to_castle ^= if castling_rights.contains('k') {
-            1 << 3
-        } else {
-            0
-        };
+        self.load_castling_rights(castling_rights);
 
         if sections.len() > 3 {
             if sections[3].len() > 1 {
                 // contains position
-                let rank = (sections[3].chars().next().unwrap().to_ascii_lowercase() as u32
-                    - 'a' as u32) as u8;
-                let file = sections[3].chars().next().unwrap().to_digit(10).unwrap() as u8 - 1;
+                let mut chars = sections[3].chars();
+                let rank = (chars.next().unwrap().to_ascii_lowercase() as u32 - 'a' as u32) as u8;
+                let file = chars.next().unwrap().to_digit(10).unwrap() as u8 - 1;
                 self.data.two_square_advance = rank + file * 8;
             }
         }
@@ -170,21 +303,386 @@ impl Board {
             self.fifty_move_counter = sections[4].parse::<u8>().unwrap_or(0);
         }
 
+        let full_moves = if sections.len() > 5 {
+            sections[5].parse::<usize>().unwrap_or(1).max(1)
+        } else {
+            1
+        };
+        self.ply = 2 * (full_moves - 1) + !self.data.white_turn as usize;
+        self.changes.clear();
+
         self.data.piece_list.populate(&self.data.board);
+        self.data.hash = ZOBRIST.hash(&self.data);
+        self.record = GameRecord::new(self.fen(), self.data.hash);
+    }
+
+    /// the current position in Forsyth-Edwards Notation, using Shredder-FEN in Chess960
+    pub fn fen(&self) -> String {
+        let mut fen = String::new();
+
+        for file in (0..8).rev() {
+            let mut empty = 0;
+            for rank in 0..8 {
+                let piece = &self.data.board[file * 8 + rank];
+                if piece.is_empty() {
+                    empty += 1;
+                    continue;
+                }
+                if empty != 0 {
+                    fen.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                fen.push(piece.as_char());
+            }
+            if empty != 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if file != 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(if self.data.white_turn { " w " } else { " b " });
+
+        // white kingside, white queenside, black kingside, black queenside
+        let mut castling_rights = String::new();
+        for (bit, standard) in [(1, 'K'), (0, 'Q'), (3, 'k'), (2, 'q')] {
+            if self.data.not_able_to_castle & (1 << bit) != 0 {
+                continue;
+            }
+            if self.data.chess960 {
+                let file = (b'a' + self.data.castling_rooks[bit] % 8) as char;
+                castling_rights.push(if bit < 2 {
+                    file.to_ascii_uppercase()
+                } else {
+                    file
+                });
+            } else {
+                castling_rights.push(standard);
+            }
+        }
+        if castling_rights.is_empty() {
+            castling_rights.push('-');
+        }
+        fen.push_str(&castling_rights);
+
+        if self.data.two_square_advance != 0 {
+            fen.push_str(&format!(
+                " {} ",
+                Position::from_index(self.data.two_square_advance)
+            ));
+        } else {
+            fen.push_str(" - ");
+        }
+
+        fen.push_str(&format!("{} {}", self.fifty_move_counter, self.ply / 2 + 1));
+        fen
+    }
+
+    /// zobrist hash of the current position
+    pub fn hash(&self) -> u64 {
+        self.data.hash
+    }
+
+    pub fn white_turn(&self) -> bool {
+        self.data.white_turn
     }
 
+    /// formats a legal move in Standard Algebraic Notation
+    pub fn move_to_san(&self, _move: &Move) -> String {
+        let mut san = if _move.castling() == MoveFlags::KINGSIDE_CASTLING {
+            String::from("O-O")
+        } else if _move.castling() == MoveFlags::QUEENSIDE_CASTLING {
+            String::from("O-O-O")
+        } else {
+            let piece = &self.data.board[_move.start as usize];
+            let start = Position::from_index(_move.start);
+            let is_capture = !self.data.board[_move.end as usize].is_empty() || _move.en_passant();
+            let mut san = String::new();
+
+            if piece.is_pawn() {
+                if is_capture {
+                    san.push((b'a' + start.rank as u8) as char);
+                }
+            } else {
+                san.push(piece.as_char().to_ascii_uppercase());
+
+                // other pieces of the same type able to move to the same square
+                let others: Vec<Position> = self
+                    .legal_moves()
+                    .iter()
+                    .filter(|m| {
+                        m.end == _move.end
+                            && m.start != _move.start
+                            && self.data.board[m.start as usize].piece == piece.piece
+                    })
+                    .map(|m| Position::from_index(m.start))
+                    .collect();
+
+                if !others.is_empty() {
+                    if others.iter().all(|p| p.rank != start.rank) {
+                        san.push((b'a' + start.rank as u8) as char);
+                    } else if others.iter().all(|p| p.file != start.file) {
+                        san.push((b'1' + start.file as u8) as char);
+                    } else {
+                        san.push_str(&start.to_string());
+                    }
+                }
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&Position::from_index(_move.end).to_string());
+
+            match _move.promotion() {
+                0 => {}
+                1 => san.push_str("=B"),
+                2 => san.push_str("=N"),
+                3 => san.push_str("=R"),
+                _ => san.push_str("=Q"),
+            }
+            san
+        };
+
+        let mut board = self.scratch();
+        board.make_move(_move);
+        if board.is_check() {
+            san.push(if board.legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        san
+    }
+
+    fn load_castling_rights(&mut self, castling_rights: &str) {
+        // supports standard FEN as well as Shredder-FEN and X-FEN, where the castling
+        // rooks are given by their file
+        self.data.castling_rooks = [0, 7, 56, 63];
+        self.data.chess960 = false;
+
+        for c in castling_rights.chars() {
+            let white = c.is_ascii_uppercase();
+            let back_rank = if white { 0 } else { 56 };
+            let color = if white { 1 } else { -1 };
+
+            let king_index = match (back_rank..back_rank + 8)
+                .find(|i| self.data.board[*i as usize].piece == 6 * color)
+            {
+                Some(index) => index,
+                None => continue,
+            };
+
+            let rook_index = match c.to_ascii_lowercase() {
+                'k' => (king_index + 1..back_rank + 8)
+                    .rev()
+                    .find(|i| self.data.board[*i as usize].piece == 4 * color),
+                'q' => (back_rank..king_index)
+                    .find(|i| self.data.board[*i as usize].piece == 4 * color),
+                file @ 'a'..='h' => Some(back_rank + (file as u8 - b'a'))
+                    .filter(|i| self.data.board[*i as usize].piece == 4 * color),
+                _ => None,
+            };
+
+            if let Some(rook_index) = rook_index {
+                let kingside = rook_index > king_index;
+                let bit = kingside as u8 + 2 * (!white as u8);
+                self.data.not_able_to_castle &= !(1 << bit);
+                self.data.castling_rooks[bit as usize] = rook_index;
+
+                if king_index % 8 != 4 || rook_index % 8 != 7 * kingside as u8 {
+                    self.data.chess960 = true;
+                }
+            }
+        }
+    }
+
+    pub fn is_chess960(&self) -> bool {
+        self.data.chess960
+    }
+
+    /// switches between standard and Chess960 notation for castling moves
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.data.chess960 = chess960;
+    }
+
+    /// formats a move in UCI notation, in Chess960 castling is encoded as king takes rook
+    pub fn move_to_uci(&self, _move: &Move) -> String {
+        let end = if self.data.chess960 && _move.castling() != 0 {
+            self.castling_rook(_move.start, _move.castling())
+        } else {
+            _move.end
+        };
+
+        let mut string = format!(
+            "{}{}",
+            Position::from_index(_move.start),
+            Position::from_index(end)
+        );
+        match _move.promotion() {
+            0 => {}
+            1 => string.push('b'),
+            2 => string.push('n'),
+            3 => string.push('r'),
+            _ => string.push('q'),
+        }
+        string
+    }
+
+    /// parses a move in UCI notation, castling may be given as king takes rook
+    pub fn move_from_uci(&self, uci: &str) -> Option<Move> {
+        let chars: Vec<char> = uci.trim().chars().collect();
+
+        if (chars.len() != 4 && chars.len() != 5)
+            || !('a'..='h').contains(&chars[0])
+            || !('1'..='8').contains(&chars[1])
+            || !('a'..='h').contains(&chars[2])
+            || !('1'..='8').contains(&chars[3])
+        {
+            return None;
+        }
+
+        let start = Position::new(chars[1] as i8 - '1' as i8, chars[0] as i8 - 'a' as i8);
+        let end = Position::new(chars[3] as i8 - '1' as i8, chars[2] as i8 - 'a' as i8);
+
+        let promotion = match chars.get(4) {
+            None => 0,
+            Some('b') => 1,
+            Some('n') => 2,
+            Some('r') => 3,
+            Some('q') => 4,
+            _ => return None,
+        };
+
+        let moves = self.legal_moves();
+        let piece = &self.data.board[start.to_index() as usize];
+        let target = &self.data.board[end.to_index() as usize];
+
+        if piece.is_king() && target.is_rook() && piece.color() == target.color() {
+            return moves.into_iter().find(|m| {
+                m.start == start.to_index()
+                    && m.castling() != 0
+                    && self.castling_rook(m.start, m.castling()) == end.to_index()
+            });
+        }
+
+        // in Chess960 a king move of two squares is never castling
+        moves.into_iter().find(|m| {
+            m.start == start.to_index()
+                && m.end == end.to_index()
+                && m.promotion() == promotion
+                && (m.castling() == 0 || !self.data.chess960)
+        })
+    }
+
+    /// parses a move in Standard Algebraic Notation, annotations like "+" or "!?" are ignored
+    pub fn move_from_san(&self, san: &str) -> Option<Move> {
+        let normalize = |san: &str| {
+            san.trim()
+                .trim_end_matches("e.p.")
+                .trim_end_matches(['+', '#', '!', '?'])
+                .replace('0', "O")
+                .replace('=', "")
+        };
+        let san = normalize(san);
+        self.legal_moves()
+            .into_iter()
+            .find(|m| normalize(&self.move_to_san(m)) == san)
+    }
+
+    #[inline(always)]
+    pub(crate) fn castling_rook(&self, king_index: u8, castling: u8) -> u8 {
+        let bit =
+            (castling == MoveFlags::KINGSIDE_CASTLING) as usize + 2 * (king_index >= 56) as usize;
+        self.data.castling_rooks[bit]
+    }
+
+    /// plays `_move` if it is legal and updates the game state
+    pub fn push(&mut self, _move: Move) -> Result<(), IllegalMove> {
+        if self.state != State::Playing || !self.is_legal(&_move) {
+            return Err(IllegalMove(_move));
+        }
+
+        let san = self.move_to_san(&_move);
+        self.make_move(&_move);
+        self.record
+            .push(RecordEntry::new(_move, san, self.fen(), self.data.hash));
+
+        self.move_generator.generate_moves(&self.data);
+        self.check_game_state();
+
+        Ok(())
+    }
+
+    /// takes back the last move played with `push`
+    pub fn pop(&mut self) -> Option<Move> {
+        let entry = self.record.pop()?;
+        self.undo_move();
+        Some(entry._move)
+    }
+
+    /// takes back the last `n` moves, returns the number of moves taken back
+    pub fn takeback(&mut self, n: usize) -> usize {
+        (0..n).take_while(|_| self.pop().is_some()).count()
+    }
+
+    /// all moves played with `push` since the position was loaded
+    pub fn record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    pub fn record_mut(&mut self) -> &mut GameRecord {
+        &mut self.record
+    }
+
+    /// the position after the first `ply` moves of the record, the board itself is unchanged
+    pub fn position_at(&self, ply: usize) -> Board {
+        let ply = ply.min(self.record.len());
+        let mut board = Board {
+            changes: self.changes.clone(),
+            record: self.record.truncated(ply),
+            ..self.scratch()
+        };
+        for _ in ply..self.record.len() {
+            board.undo_move();
+        }
+        board
+    }
+
+    /// a copy of the position to try moves on, without the moves made before and the record
+    fn scratch(&self) -> Board {
+        Board {
+            move_generator: MoveGenerator::new(),
+            data: self.data.clone(),
+            state: self.state,
+            ply: self.ply,
+            fifty_move_counter: self.fifty_move_counter,
+            changes: Vec::new(),
+            record: GameRecord::new(String::new(), self.data.hash),
+        }
+    }
+
+    /// all positions of the game, starting with the position the game started from
+    pub fn positions(&self) -> impl Iterator<Item = Board> + '_ {
+        (0..=self.record.len()).map(|ply| self.position_at(ply))
+    }
+
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// make a move without checking if it is legal, prefer `push` outside of the search
     pub(crate) fn make_move(&mut self, _move: &Move) {
         let mut piece = self.data.board[_move.start as usize].clone();
 
         self.data.board[_move.start as usize] = Piece::empty();
 
-        let fifty_move_counter = self.fifty_move_counter;
+        let hash = self.data.hash;
+        self.data.hash ^= ZOBRIST.piece(&piece, _move.start);
 
-        if !piece.is_pawn() && self.data.board[_move.end as usize].is_empty() {
-            self.fifty_move_counter += 1;
-        } else {
-            self.fifty_move_counter = 0;
-        }
+        let fifty_move_counter = self.fifty_move_counter;
 
         let captured_position = if _move.en_passant() {
             (self.data.two_square_advance as i8 - 8 * piece.color()) as u8
@@ -192,7 +690,18 @@ impl Board {
             _move.end
         };
 
-        let mut captured = self.data.board[captured_position as usize].clone();
+        // in Chess960 the king might castle onto the square of its own rook
+        let mut captured = if _move.castling() != 0 {
+            Piece::empty()
+        } else {
+            self.data.board[captured_position as usize].clone()
+        };
+
+        if !piece.is_pawn() && captured.is_empty() {
+            self.fifty_move_counter += 1;
+        } else {
+            self.fifty_move_counter = 0;
+        }
 
         debug_assert!(!captured.is_king());
 
@@ -207,6 +716,10 @@ impl Board {
             self.data.piece_list.remove(&captured);
         }
 
+        if !captured.is_empty() {
+            self.data.hash ^= ZOBRIST.piece(&captured, captured_position);
+        }
+
         if _move.promotion() != 0 {
             self.data.piece_list.remove(&piece);
             piece.promotion(_move.promotion());
@@ -217,11 +730,7 @@ impl Board {
         let not_able_to_castle = self.data.not_able_to_castle;
 
         if _move.castling() != 0 {
-            let old_rook_pos = if _move.castling() == MoveFlags::QUEENSIDE_CASTLING {
-                0
-            } else {
-                7
-            } + ((_move.start as usize) / 8) * 8;
+            let old_rook_pos = self.castling_rook(_move.start, _move.castling()) as usize;
             let new_rook_pos = if _move.castling() == MoveFlags::QUEENSIDE_CASTLING {
                 3
             } else {
@@ -229,12 +738,15 @@ impl Board {
             } + ((_move.start as usize) / 8) * 8;
 
             let rook = self.data.board[old_rook_pos].clone();
-            debug_assert!(!rook.is_empty());
+            debug_assert!(rook.is_rook());
 
             self.data.board[old_rook_pos] = Piece::empty();
 
             debug_assert!(self.data.board[new_rook_pos].is_empty());
 
+            self.data.hash ^= ZOBRIST.piece(&rook, old_rook_pos as u8);
+            self.data.hash ^= ZOBRIST.piece(&rook, new_rook_pos as u8);
+
             self.data.piece_list._move(&rook, new_rook_pos as u8);
             self.data.board[new_rook_pos] = rook;
         }
@@ -247,33 +759,35 @@ impl Board {
 
         if piece.is_king() {
             self.data.not_able_to_castle |= 0x03 << (2 * (!self.data.white_turn as u8));
-        } else if piece.is_rook() && (_move.start == 0 || _move.start == 7) {
-            // white castling
-            self.data.not_able_to_castle |= 0x01 << ((_move.start != 0) as u8);
-        } else if piece.is_rook() && (_move.start == 56 || _move.start == 63) {
-            // black castling
-            self.data.not_able_to_castle |= 0x04 << ((_move.start != 56) as u8);
-        } else if captured.is_rook() && (_move.end == 0 || _move.end == 7) {
-            // white castling
-            self.data.not_able_to_castle |= 0x01 << ((_move.end != 0) as u8);
-        } else if captured.is_rook() && (_move.end == 56 || _move.end == 63) {
-            // black castling
-            self.data.not_able_to_castle |= 0x04 << ((_move.end != 56) as u8);
+        }
+        for (i, rook_pos) in self.data.castling_rooks.iter().enumerate() {
+            // moving or capturing a castling rook
+            if *rook_pos == _move.start || (*rook_pos == _move.end && _move.castling() == 0) {
+                self.data.not_able_to_castle |= 1 << i;
+            }
         }
 
         self.changes.push(Change::new(
             _move.start,
             _move.end,
             captured,
-            captured_position as u8,
+            captured_position,
             two_square_advance,
             fifty_move_counter,
             _move.promotion() != 0,
             _move.en_passant(),
             not_able_to_castle,
             _move.castling(),
+            hash,
         ));
 
+        self.data.hash ^= ZOBRIST.castling(not_able_to_castle)
+            ^ ZOBRIST.castling(self.data.not_able_to_castle)
+            ^ ZOBRIST.en_passant(two_square_advance)
+            ^ ZOBRIST.en_passant(self.data.two_square_advance)
+            ^ ZOBRIST.side()
+            ^ ZOBRIST.piece(&piece, _move.end);
+
         self.data.piece_list._move(&piece, _move.end);
         self.data.board[_move.end as usize] = piece;
 
@@ -281,6 +795,7 @@ impl Board {
         self.data.white_turn = !self.data.white_turn;
     }
 
+    /// undo the last move made with `make_move`
     pub(crate) fn undo_move(&mut self) {
         self.state = State::Playing;
         let change = self.changes.pop().expect("No move to undo");
@@ -290,6 +805,7 @@ impl Board {
         self.fifty_move_counter = change.fifty_move_counter;
         self.data.not_able_to_castle = change.not_able_to_castle;
         self.data.two_square_advance = change.two_square_advance;
+        self.data.hash = change.hash;
 
         if !change.captured.is_empty() {
             self.data
@@ -311,11 +827,7 @@ impl Board {
         }
 
         if change.castling != 0 {
-            let new_rook_pos = if change.castling == MoveFlags::QUEENSIDE_CASTLING {
-                0
-            } else {
-                7
-            } + ((change.end as usize) / 8) * 8;
+            let new_rook_pos = self.castling_rook(change.start, change.castling) as usize;
             let old_rook_pos = if change.castling == MoveFlags::QUEENSIDE_CASTLING {
                 3
             } else {
@@ -323,7 +835,7 @@ impl Board {
             } + ((change.end as usize) / 8) * 8;
 
             let rook = self.data.board[old_rook_pos].clone();
-            debug_assert!(!rook.is_empty());
+            debug_assert!(rook.is_rook());
 
             self.data.board[old_rook_pos] = Piece::empty();
 
@@ -340,6 +852,18 @@ impl Board {
         self.data.white_turn = !self.data.white_turn;
     }
 
+    /// `make_move` for the benchmarks, which measure it without the checks of `push`
+    #[doc(hidden)]
+    pub fn bench_make_move(&mut self, _move: &Move) {
+        self.make_move(_move);
+    }
+
+    /// `undo_move` for the benchmarks
+    #[doc(hidden)]
+    pub fn bench_undo_move(&mut self) {
+        self.undo_move();
+    }
+
     #[inline(always)]
     pub(crate) fn pawn_on_original_position(&self, index: u8, color: i8) -> bool {
         Position::from_index(index).file == 1 - 5 * (color - 1) / 2
@@ -359,6 +883,7 @@ impl Board {
         self.move_generator.generate_moves(&self.data);
     }
 
+    #[allow(clippy::only_used_in_recursion)]
     pub fn benchmark(mut self, depth: u8, start: u8) -> (Self, usize, u128) {
         let now = std::time::Instant::now();
 
@@ -387,7 +912,7 @@ impl Board {
         // let board = self.data.board.clone();
 
         for (i, _move) in self.move_generator.moves.clone().iter().enumerate() {
-            self.make_move(&_move);
+            self.make_move(_move);
 
             let _num_positions;
 
@@ -403,92 +928,85 @@ impl Board {
         (self, num_positions, now.elapsed().as_micros())
     }
 
+    /// whether the player could checkmate with any series of legal moves as by the FIDE rules.
+    /// A lone king can not, neither can a king and knight against a king with at most queens,
+    /// nor bishops if all pieces besides the kings are bishops on squares of one color
+    pub fn has_mating_material(&self, white: bool) -> bool {
+        let color = if white { 1 } else { -1 };
+        // square and type of the pieces besides the kings
+        let pieces = |color: i8| -> Vec<(usize, i8)> {
+            self.data
+                .board
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| p.color() == color && !p.is_king())
+                .map(|(i, p)| (i, p.piece.abs()))
+                .collect()
+        };
+        let (own, opponent) = (pieces(color), pieces(-color));
+        if own.iter().any(|(_, piece)| matches!(piece, 1 | 4 | 5)) {
+            return true;
+        }
+        if own.iter().any(|(_, piece)| *piece == 3) {
+            // the opponent's own pieces have to block the flight squares of their king
+            return own.len() > 1 || opponent.iter().any(|(_, piece)| *piece != 5);
+        }
+        let square_color = |index: usize| (index / 8 + index % 8) % 2;
+        match own.first() {
+            Some((first, _)) => !own
+                .iter()
+                .chain(&opponent)
+                .all(|(i, piece)| *piece == 2 && square_color(*i) == square_color(*first)),
+            None => false,
+        }
+    }
+
     pub fn check_game_state(&mut self) {
         // depends on previous call to MoveGenerator::generate_moves
 
         // checkmate or stalemate
-        if self.move_generator.moves.len() == 0 {
+        if self.move_generator.moves.is_empty() {
             if self.move_generator.in_check {
-                self.state = if self.data.white_turn {
-                    State::WhiteIsMated
-                } else {
-                    State::BlackIsMated
-                };
+                self.state = State::Over(GameResult::win_for(
+                    !self.data.white_turn,
+                    Reason::Checkmate,
+                ));
                 return;
             } else {
-                self.state = State::Stalemate;
+                self.state = State::Over(GameResult::draw(Reason::Stalemate));
                 return;
             }
         }
 
         // fifty move rule
         if self.fifty_move_counter >= 100 {
-            self.state = State::FiftyMoveRule;
+            self.state = State::Over(GameResult::draw(Reason::FiftyMoveRule));
+            return;
         }
 
-        // TODO: check for threefold repetition
+        // threefold repetition
+        let reversible = self.fifty_move_counter as usize;
+        if self.record.repetitions(self.data.hash, reversible) >= 3 {
+            self.state = State::Over(GameResult::draw(Reason::Repetition));
+            return;
+        }
 
         // insufficient material
         if self.data.piece_list.pawn_count()
             + self.data.piece_list.rook_count()
             + self.data.piece_list.queen_count()
             == 0
+            && self.data.piece_list.knight_count() + self.data.piece_list.bishop_count() <= 1
         {
-            if self.data.piece_list.knight_count() + self.data.piece_list.bishop_count() <= 1 {
-                // only king v king, king v king + bishop or king v king + knight
-                self.state = State::InsufficientMaterial;
-                return;
-            }
-        }
-    }
-
-    pub fn play_ply(&mut self) -> bool {
-        if self.state != State::Playing {
-            return true;
-        }
-
-        self.move_generator.generate_moves(&self.data);
-
-        self.check_game_state();
-
-        if self.state != State::Playing {
-            return true;
-        }
-
-        let player = self.players[self.ply % 2];
-
-        let _move = player._move(self);
-
-        self.make_move(&_move);
-
-        false
-    }
-
-    pub fn play_human_ply(&mut self, start: Position, end: Position) -> bool {
-        if self.state != State::Playing {
-            return true;
+            // only king v king, king v king + bishop or king v king + knight
+            self.state = State::Over(GameResult::draw(Reason::InsufficientMaterial));
         }
-
-        self.move_generator.generate_moves(&self.data);
-
-        self.check_game_state();
-
-        if self.state != State::Playing {
-            return true;
-        }
-
-        let player = self.players[self.ply % 2];
-        assert!(player == PlayerType::HumanPlayer);
-
-        let _move = self.move_generator.get_move(start, end, 0);
-
-        self.make_move(&_move.unwrap());
-
-        false
     }
+}
 
-    pub fn human_turn(&self) -> bool {
-        self.players[self.ply % 2] == PlayerType::HumanPlayer
+impl Default for Board {
+    fn default() -> Self {
+        Board::new()
     }
 }
 
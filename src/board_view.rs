@@ -0,0 +1,478 @@
+use chess::position::Position;
+use chess::{Board, Piece, PieceColor, PieceType};
+use iced::alignment::{Horizontal, Vertical};
+use iced::{Color, Element, Font, Length, Point, Rectangle, Renderer, Size, Theme};
+use iced_native::widget::{tree, Tree};
+use iced_native::{event, layout, mouse, renderer, text, Clipboard, Event, Layout, Shell, Widget};
+
+use crate::{Message, MESLO_LG_FONT};
+
+const LIGHT_SQUARE: Color = Color::from_rgb(0.93, 0.93, 0.82);
+const DARK_SQUARE: Color = Color::from_rgb(0.46, 0.59, 0.34);
+const SELECTED: Color = Color::from_rgba(1.0, 0.9, 0.2, 0.5);
+const WHITE_PIECE: Color = Color::from_rgb(0.9, 0.9, 0.9);
+const BLACK_PIECE: Color = Color::from_rgb(0.1, 0.1, 0.1);
+const SHADE: Color = Color::from_rgba(0.0, 0.0, 0.0, 0.6);
+const PICKER: Color = Color::from_rgb(0.6, 0.6, 0.6);
+const LAST_MOVE: Color = Color::from_rgba(0.6, 0.8, 1.0, 0.45);
+const CHECK: Color = Color::from_rgba(0.9, 0.1, 0.1, 0.7);
+const TARGET: Color = Color::from_rgba(0.0, 0.0, 0.0, 0.25);
+
+/// the pieces offered for a promotion, from the promotion square towards the center
+const PROMOTIONS: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Knight,
+    PieceType::Rook,
+    PieceType::Bishop,
+];
+
+/// the chess board, a piece is moved by dragging it to the target square or by clicking the
+/// piece and then the target. Picking a piece up sends `Message::DragMessage`, dropping it or
+/// clicking a target sends `Message::DropMessage` with the square, `None` outside the board.
+/// While a promotion is pending the pieces to choose are shown on the promotion square, a click
+/// sends `Message::PromoteMessage` with the piece or `None` to take the move back.
+/// The last move, a king in check and the squares the selected piece can move to are marked
+pub struct BoardView<'a> {
+    board: &'a Board,
+    selected: Option<Position>,
+    /// whether the pieces can be moved
+    interactive: bool,
+    /// the square a pawn is promoted on once the piece is chosen
+    promotion: Option<Position>,
+    /// whether black is shown at the bottom
+    flipped: bool,
+}
+
+/// the piece being dragged and the position of the cursor
+#[derive(Default)]
+struct State {
+    drag: Option<(Position, Point)>,
+}
+
+impl<'a> BoardView<'a> {
+    pub fn new(board: &'a Board, selected: Option<Position>, interactive: bool) -> Self {
+        BoardView {
+            board,
+            selected,
+            interactive,
+            promotion: None,
+            flipped: false,
+        }
+    }
+
+    /// shows the board from the side of black if `flipped`
+    pub fn flipped(mut self, flipped: bool) -> Self {
+        self.flipped = flipped;
+        self
+    }
+
+    /// shows the pieces to promote to on `square`
+    pub fn promoting(mut self, square: Option<Position>) -> Self {
+        self.promotion = square;
+        self
+    }
+}
+
+/// the squares of the pieces offered for a promotion on `square`
+fn picker_squares(square: Position) -> impl Iterator<Item = Position> {
+    let step = if square.file == 7 { -1 } else { 1 };
+    (0..4).map(move |i| Position::new(square.file + i * step, square.rank))
+}
+
+/// the largest square with the board centered in the bounds of the widget
+fn board_bounds(bounds: Rectangle) -> Rectangle {
+    let side = bounds.width.min(bounds.height);
+    Rectangle {
+        x: bounds.x + (bounds.width - side) / 2.0,
+        y: bounds.y + (bounds.height - side) / 2.0,
+        width: side,
+        height: side,
+    }
+}
+
+fn square_bounds(board: Rectangle, pos: Position, flipped: bool) -> Rectangle {
+    let size = board.width / 8.0;
+    let (column, row) = if flipped {
+        (7 - pos.rank, pos.file)
+    } else {
+        (pos.rank, 7 - pos.file)
+    };
+    Rectangle {
+        x: board.x + column as f32 * size,
+        y: board.y + row as f32 * size,
+        width: size,
+        height: size,
+    }
+}
+
+fn square_at(board: Rectangle, point: Point, flipped: bool) -> Option<Position> {
+    if !board.contains(point) {
+        return None;
+    }
+    let size = board.width / 8.0;
+    let column = (((point.x - board.x) / size) as i8).min(7);
+    let row = (((point.y - board.y) / size) as i8).min(7);
+    if flipped {
+        Some(Position::new(row, 7 - column))
+    } else {
+        Some(Position::new(7 - row, column))
+    }
+}
+
+impl BoardView<'_> {
+    fn draw_piece(&self, renderer: &mut Renderer, pos: Position, center: Point, size: f32) {
+        draw_glyph(
+            renderer,
+            &self.board.get_piece_at_position(pos),
+            center,
+            size,
+        );
+    }
+
+    /// the rank numbers along the left edge and the file letters along the bottom edge
+    fn draw_coordinates(
+        &self,
+        renderer: &mut Renderer,
+        pos: Position,
+        bounds: Rectangle,
+        color: Color,
+    ) {
+        let edge = if self.flipped { 7 } else { 0 };
+        let size = bounds.width / 5.0;
+        let mut label = |content: &str, x, y, horizontal_alignment, vertical_alignment| {
+            text::Renderer::fill_text(
+                renderer,
+                text::Text {
+                    content,
+                    bounds: Rectangle {
+                        x,
+                        y,
+                        width: bounds.width,
+                        height: bounds.height,
+                    },
+                    size,
+                    color,
+                    font: Font::Default,
+                    horizontal_alignment,
+                    vertical_alignment,
+                },
+            );
+        };
+        let margin = bounds.width / 20.0;
+        if pos.rank == edge {
+            let number = (pos.file + 1).to_string();
+            let (x, y) = (bounds.x + margin, bounds.y + margin);
+            label(&number, x, y, Horizontal::Left, Vertical::Top);
+        }
+        if pos.file == edge {
+            let letter = ((b'a' + pos.rank as u8) as char).to_string();
+            let (x, y) = (
+                bounds.x + bounds.width - margin,
+                bounds.y + bounds.height - margin,
+            );
+            label(&letter, x, y, Horizontal::Right, Vertical::Bottom);
+        }
+    }
+
+    /// the pieces to promote to above the shaded board
+    fn draw_picker(&self, renderer: &mut Renderer, board: Rectangle, square: Position) {
+        let size = board.width / 8.0;
+        let shade = renderer::Quad {
+            bounds: board,
+            border_radius: 0.0,
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+        };
+        renderer::Renderer::fill_quad(renderer, shade, SHADE);
+        let color = if self.board.white_turn() {
+            PieceColor::White
+        } else {
+            PieceColor::Black
+        };
+        for (pos, piece_type) in picker_squares(square).zip(PROMOTIONS) {
+            let bounds = square_bounds(board, pos, self.flipped);
+            let quad = renderer::Quad {
+                bounds,
+                border_radius: size / 2.0,
+                border_width: 0.0,
+                border_color: Color::TRANSPARENT,
+            };
+            renderer::Renderer::fill_quad(renderer, quad, PICKER);
+            let mut piece = Piece::empty();
+            piece.set(piece_type, color, 0);
+            draw_glyph(renderer, &piece, bounds.center(), size);
+        }
+    }
+}
+
+/// a dot on an empty square the selected piece can move to, a ring around a piece it can take
+fn draw_target(renderer: &mut Renderer, bounds: Rectangle, empty: bool) {
+    let size = bounds.width;
+    let quad = if empty {
+        renderer::Quad {
+            bounds: Rectangle {
+                x: bounds.x + size / 3.0,
+                y: bounds.y + size / 3.0,
+                width: size / 3.0,
+                height: size / 3.0,
+            },
+            border_radius: size / 6.0,
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+        }
+    } else {
+        renderer::Quad {
+            bounds,
+            border_radius: size / 2.0,
+            border_width: size / 12.0,
+            border_color: TARGET,
+        }
+    };
+    let fill = if empty { TARGET } else { Color::TRANSPARENT };
+    renderer::Renderer::fill_quad(renderer, quad, fill);
+}
+
+fn draw_glyph(renderer: &mut Renderer, piece: &Piece, center: Point, size: f32) {
+    if piece.is_empty() {
+        return;
+    }
+    text::Renderer::fill_text(
+        renderer,
+        text::Text {
+            content: &piece.as_unicode_char_abs().to_string(),
+            bounds: Rectangle {
+                x: center.x,
+                y: center.y,
+                width: size,
+                height: size,
+            },
+            size: size * 0.9,
+            color: if piece.is_white() {
+                WHITE_PIECE
+            } else {
+                BLACK_PIECE
+            },
+            font: MESLO_LG_FONT,
+            horizontal_alignment: Horizontal::Center,
+            vertical_alignment: Vertical::Center,
+        },
+    );
+}
+
+impl Widget<Message, Renderer> for BoardView<'_> {
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Fill
+    }
+
+    fn layout(&self, _renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        layout::Node::new(
+            limits
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .resolve(Size::ZERO),
+        )
+    }
+
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+        let board = board_bounds(layout.bounds());
+        if let Some(square) = self.promotion {
+            if let Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event {
+                let clicked = square_at(board, cursor_position, self.flipped);
+                let choice = picker_squares(square)
+                    .zip(PROMOTIONS)
+                    .find(|(pos, _)| Some(*pos) == clicked)
+                    .map(|(_, piece_type)| piece_type);
+                shell.publish(Message::PromoteMessage(choice));
+                return event::Status::Captured;
+            }
+            return event::Status::Ignored;
+        }
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) if self.interactive => {
+                let pos = match square_at(board, cursor_position, self.flipped) {
+                    Some(pos) => pos,
+                    None => return event::Status::Ignored,
+                };
+                let target = self
+                    .selected
+                    .is_some_and(|from| self.board.piece_able_to_move_to_pos(from, pos));
+                if !target && self.board.piece_able_to_move(pos) {
+                    state.drag = Some((pos, cursor_position));
+                    shell.publish(Message::DragMessage(pos));
+                } else {
+                    // moves the selected piece or cancels the selection
+                    shell.publish(Message::DropMessage(Some(pos)));
+                }
+                event::Status::Captured
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => match &mut state.drag {
+                Some((_, point)) => {
+                    *point = position;
+                    event::Status::Captured
+                }
+                None => event::Status::Ignored,
+            },
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                match state.drag.take() {
+                    // released where it was picked up the piece stays selected for a click on
+                    // the target
+                    Some((from, _))
+                        if square_at(board, cursor_position, self.flipped) == Some(from) => {}
+                    Some(_) => shell.publish(Message::DropMessage(square_at(
+                        board,
+                        cursor_position,
+                        self.flipped,
+                    ))),
+                    None => return event::Status::Ignored,
+                }
+                event::Status::Captured
+            }
+            _ => event::Status::Ignored,
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        if tree.state.downcast_ref::<State>().drag.is_some() {
+            return mouse::Interaction::Grabbing;
+        }
+        let hovered = square_at(board_bounds(layout.bounds()), cursor_position, self.flipped);
+        if let Some(square) = self.promotion {
+            return if picker_squares(square).any(|pos| Some(pos) == hovered) {
+                mouse::Interaction::Pointer
+            } else {
+                mouse::Interaction::Idle
+            };
+        }
+        let movable =
+            hovered.is_some_and(|pos| self.interactive && self.board.piece_able_to_move(pos));
+        if movable {
+            mouse::Interaction::Grab
+        } else {
+            mouse::Interaction::Idle
+        }
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        viewport: &Rectangle,
+    ) {
+        let board = board_bounds(layout.bounds());
+        let size = board.width / 8.0;
+        let drag = tree.state.downcast_ref::<State>().drag;
+        // the square the dragged piece would snap to
+        let target = drag.and_then(|(from, point)| {
+            square_at(board, point, self.flipped)
+                .filter(|pos| self.board.piece_able_to_move_to_pos(from, *pos))
+        });
+        let last_move = self.board.last_move();
+        let checked_king = self.board.checked_king();
+        let moves = self
+            .selected
+            .map(|from| self.board.moves_from(from))
+            .unwrap_or_default();
+
+        for file in 0..8 {
+            for rank in 0..8 {
+                let pos = Position::new(file, rank);
+                let bounds = square_bounds(board, pos, self.flipped);
+                let quad = |border_width, border_color| renderer::Quad {
+                    bounds,
+                    border_radius: 0.0,
+                    border_width,
+                    border_color,
+                };
+                let (color, other) = if (file + rank) % 2 == 0 {
+                    (DARK_SQUARE, LIGHT_SQUARE)
+                } else {
+                    (LIGHT_SQUARE, DARK_SQUARE)
+                };
+                renderer::Renderer::fill_quad(renderer, quad(0.0, Color::TRANSPARENT), color);
+                if last_move.is_some_and(|(from, to)| pos == from || pos == to) {
+                    renderer::Renderer::fill_quad(
+                        renderer,
+                        quad(0.0, Color::TRANSPARENT),
+                        LAST_MOVE,
+                    );
+                }
+                if checked_king == Some(pos) {
+                    renderer::Renderer::fill_quad(renderer, quad(0.0, Color::TRANSPARENT), CHECK);
+                }
+                self.draw_coordinates(renderer, pos, bounds, other);
+                if self.selected == Some(pos) {
+                    renderer::Renderer::fill_quad(
+                        renderer,
+                        quad(0.0, Color::TRANSPARENT),
+                        SELECTED,
+                    );
+                }
+                if target == Some(pos) {
+                    let outline = quad(size / 16.0, SELECTED);
+                    renderer::Renderer::fill_quad(renderer, outline, Color::TRANSPARENT);
+                }
+                if drag.map(|(from, _)| from) != Some(pos) {
+                    self.draw_piece(renderer, pos, bounds.center(), size);
+                }
+                if moves.iter().any(|_move| _move.end == pos.to_index()) {
+                    draw_target(
+                        renderer,
+                        bounds,
+                        self.board.get_piece_at_position(pos).is_empty(),
+                    );
+                }
+            }
+        }
+
+        // the dragged piece follows the cursor above the board
+        if let Some((from, point)) = drag {
+            renderer::Renderer::with_layer(renderer, *viewport, |renderer| {
+                self.draw_piece(renderer, from, point, size)
+            });
+        }
+        if let Some(square) = self.promotion {
+            renderer::Renderer::with_layer(renderer, *viewport, |renderer| {
+                self.draw_picker(renderer, board, square)
+            });
+        }
+    }
+}
+
+impl<'a> From<BoardView<'a>> for Element<'a, Message> {
+    fn from(board: BoardView<'a>) -> Self {
+        Element::new(board)
+    }
+}
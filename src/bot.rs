@@ -1,4 +1,16 @@
-use crate::{state::State, Board, Move, PieceType};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::game_result::{GameResult, Outcome, Reason};
+use crate::syzygy::{Syzygy, Wdl};
+use crate::tablebase::{Probe, Tablebases};
+use crate::transposition::{Bound, Entry, TranspositionTable};
+use crate::{Board, Move, PieceType};
 
 fn material_value(board: &Board) -> i32 {
     let mut sum = 0;
@@ -13,56 +25,769 @@ fn evaluate_position(board: &Board) -> i32 {
     material_value(board) * if board.data.white_turn { 1 } else { -1 }
 }
 
-fn search_moves(board: &mut Board, depth: i32, mut alpha: i32, beta: i32) -> (i32, Option<Move>) {
-    let now = std::time::Instant::now();
-    let mut _best_move = None;
+/// evaluation of a checkmate at the root, mates further away score one less per ply
+const MATE: i32 = i32::MAX - 1;
+
+/// the most plies to a mate encoded in an evaluation, searches and tablebase lines are shorter
+const MAX_MATE_PLIES: i32 = 1000;
+
+/// evaluation of a position `ply` plies from the root, mated `plies` plies later
+fn mated_in(ply: i32, plies: i32) -> i32 {
+    -(MATE - ply - plies)
+}
+
+fn tablebase_evaluation(probe: Probe, ply: i32) -> i32 {
+    match probe {
+        Probe::Win(plies) => -mated_in(ply, plies as i32),
+        Probe::Draw => 0,
+        Probe::Loss(plies) => mated_in(ply, plies as i32),
+    }
+}
+
+/// the evaluation stored in the transposition table, mates counted from the position instead
+/// of the root
+fn to_table(evaluation: i32, ply: i32) -> i32 {
+    if evaluation.abs() >= MATE - MAX_MATE_PLIES {
+        evaluation + evaluation.signum() * ply
+    } else {
+        evaluation
+    }
+}
+
+fn from_table(evaluation: i32, ply: i32) -> i32 {
+    if evaluation.abs() >= MATE - MAX_MATE_PLIES {
+        evaluation - evaluation.signum() * ply
+    } else {
+        evaluation
+    }
+}
+
+/// evaluation of a position won according to the Syzygy tables, which do not know the distance
+/// to mate
+const SYZYGY_WIN: i32 = i32::MAX / 4;
 
-    if depth == 0 {
-        return (evaluate_position(board), None);
+fn syzygy_evaluation(wdl: Wdl) -> i32 {
+    match wdl {
+        Wdl::Win => SYZYGY_WIN,
+        Wdl::Loss => -SYZYGY_WIN,
+        // drawn by the fifty move rule
+        Wdl::CursedWin | Wdl::Draw | Wdl::BlessedLoss => 0,
     }
+}
 
-    board.generate_moves();
+/// the state of one search, the tables positions are looked up in and its progress
+struct Searcher<'a> {
+    tablebases: Option<&'a Tablebases>,
+    syzygy: Option<&'a Syzygy>,
+    table: Option<&'a TranspositionTable>,
+    stop: Option<&'a AtomicBool>,
+    /// while set the budget does not end the search
+    pondering: Option<&'a AtomicBool>,
+    /// the search stops after this many nodes
+    node_limit: Option<u64>,
+    /// the search stops once this time is reached, unless it ponders
+    deadline: Option<Instant>,
+    /// whether the search was stopped before finishing the current iteration
+    stopped: bool,
+    nodes: u64,
+    /// nodes of the helper threads of a parallel search, reported by the main thread
+    helper_nodes: Option<&'a AtomicU64>,
+    /// the count of the main thread a helper adds its nodes to
+    count: Option<&'a AtomicU64>,
+    /// the most plies from the root reached
+    seldepth: i32,
+}
 
-    board.check_game_state();
+impl<'a> Searcher<'a> {
+    fn new(tablebases: Option<&'a Tablebases>, syzygy: Option<&'a Syzygy>) -> Searcher<'a> {
+        Searcher {
+            tablebases,
+            syzygy,
+            table: None,
+            stop: None,
+            pondering: None,
+            node_limit: None,
+            deadline: None,
+            stopped: false,
+            nodes: 0,
+            helper_nodes: None,
+            count: None,
+            seldepth: 0,
+        }
+    }
 
-    if board.move_generator.moves.len() == 0 {
-        if board.state == State::WhiteIsMated || board.state == State::BlackIsMated {
-            return if (board.state == State::WhiteIsMated) == board.data.white_turn {
-                (i32::MIN + 1, None)
+    /// searches the current position, returns the evaluation and the principal variation
+    fn search_moves(
+        &mut self,
+        board: &mut Board,
+        depth: i32,
+        ply: i32,
+        alpha: i32,
+        beta: i32,
+    ) -> (i32, Vec<Move>) {
+        if let Some(probe) = self
+            .tablebases
+            .and_then(|tablebases| tablebases.probe(board))
+        {
+            return (tablebase_evaluation(probe, ply), Vec::new());
+        }
+
+        if depth == 0 {
+            return (evaluate_position(board), Vec::new());
+        }
+
+        board.generate_moves();
+
+        board.check_game_state();
+
+        if board.move_generator.moves.is_empty() {
+            if let Some(GameResult {
+                outcome,
+                reason: Reason::Checkmate,
+            }) = board.state.result()
+            {
+                return if (outcome == Outcome::BlackWins) == board.data.white_turn {
+                    (mated_in(ply, 0), Vec::new())
+                } else {
+                    (-mated_in(ply, 0), Vec::new())
+                };
+            }
+            return (0, Vec::new());
+        }
+
+        let mut moves = board.move_generator.moves.clone();
+        let table = match self.table {
+            Some(table) => table,
+            None => return self.search_list(board, &moves, depth, ply, alpha, beta),
+        };
+
+        // a bound outside the window cuts off, an exact score inside it would lack the variation
+        let entry = table.probe(board.hash());
+        if let Some(entry) = entry.as_ref().filter(|entry| entry.depth >= depth) {
+            let score = from_table(entry.score, ply);
+            if score >= beta && entry.bound != Bound::Upper {
+                return (beta, Vec::new());
+            }
+            if score <= alpha && entry.bound != Bound::Lower {
+                return (alpha, Vec::new());
+            }
+        }
+        // the best move found before is searched first
+        if let Some(best) = entry.and_then(|entry| entry.best_move) {
+            if let Some(i) = moves.iter().position(|_move| *_move == best) {
+                moves[..=i].rotate_right(1);
+            }
+        }
+
+        let (score, variation) = self.search_list(board, &moves, depth, ply, alpha, beta);
+        if !self.stopped {
+            let bound = if score <= alpha {
+                Bound::Upper
+            } else if score >= beta {
+                Bound::Lower
             } else {
-                (i32::MAX, None)
+                Bound::Exact
+            };
+            let best_move = variation.first().cloned();
+            table.store(
+                board.hash(),
+                Entry {
+                    depth,
+                    score: to_table(score, ply),
+                    bound,
+                    best_move,
+                },
+            );
+        }
+        (score, variation)
+    }
+
+    /// searches `moves` of the current position, returns the evaluation and the principal
+    /// variation
+    fn search_list(
+        &mut self,
+        board: &mut Board,
+        moves: &[Move],
+        depth: i32,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> (i32, Vec<Move>) {
+        let mut variation = Vec::new();
+
+        for _move in moves {
+            if self.stopped
+                || self.stop.is_some_and(|stop| stop.load(Ordering::Relaxed))
+                || self.node_limit.is_some_and(|limit| self.nodes >= limit)
+                || self.out_of_time()
+            {
+                self.stopped = true;
+                break;
+            }
+            board.make_move(_move);
+            self.nodes += 1;
+            if self.nodes.is_multiple_of(1024) {
+                if let Some(count) = self.count {
+                    count.fetch_add(1024, Ordering::Relaxed);
+                }
+            }
+            self.seldepth = self.seldepth.max(ply + 1);
+
+            let (evaluation, line) = match self.syzygy.and_then(|syzygy| syzygy.probe_wdl(board)) {
+                Some(wdl) => (syzygy_evaluation(wdl), Vec::new()),
+                None => self.search_moves(board, depth - 1, ply + 1, -beta, -alpha),
             };
+
+            board.undo_move();
+
+            if -evaluation >= beta {
+                return (beta, std::iter::once(_move.clone()).chain(line).collect());
+            }
+            if -evaluation > alpha {
+                alpha = -evaluation;
+                variation = std::iter::once(_move.clone()).chain(line).collect();
+            }
+        }
+        (alpha, variation)
+    }
+
+    /// whether the deadline passed, the clock is only read every 1024 nodes
+    fn out_of_time(&self) -> bool {
+        self.nodes.is_multiple_of(1024)
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            && !self
+                .pondering
+                .is_some_and(|pondering| pondering.load(Ordering::Relaxed))
+    }
+
+    /// the best `count` lines of the current position, best first. Each line is searched
+    /// without the first moves of the lines found before
+    fn lines(&mut self, board: &mut Board, depth: i32, count: usize) -> Vec<Line> {
+        let mut moves = board.legal_moves();
+        let mut lines = Vec::new();
+
+        while lines.len() < count && !moves.is_empty() && !self.stopped {
+            let (evaluation, mut variation) =
+                self.search_list(board, &moves, depth.max(1), 0, i32::MIN + 1, i32::MAX);
+            // every move left gets mated
+            if variation.is_empty() {
+                variation.push(moves[0].clone());
+            }
+            moves.retain(|_move| *_move != variation[0]);
+            lines.push(Line {
+                moves: variation,
+                score: centipawns(evaluation),
+            });
+        }
+        lines
+    }
+
+    fn report(
+        &self,
+        observer: &mut dyn SearchObserver,
+        depth: i32,
+        start: Instant,
+        lines: &[Line],
+    ) {
+        let time = start.elapsed();
+        let nodes = self.nodes
+            + self
+                .helper_nodes
+                .map_or(0, |nodes| nodes.load(Ordering::Relaxed));
+        let nps = nodes as u128 * 1_000_000 / time.as_micros().max(1);
+        for (i, line) in lines.iter().enumerate() {
+            observer.iteration(&SearchInfo {
+                depth,
+                seldepth: self.seldepth,
+                multipv: i + 1,
+                line: line.clone(),
+                nodes,
+                nps: nps as u64,
+                hashfull: self.table.map_or(0, TranspositionTable::hashfull),
+                time,
+            });
+        }
+    }
+}
+
+/// the deepest iteration of a search without a limit, ex.: analysis until stopped
+pub const MAX_DEPTH: i32 = 64;
+
+/// rough estimate of how much longer a search one ply deeper takes
+const BRANCHING_FACTOR: u32 = 6;
+
+/// the search is stopped after this many times the budget, even within an iteration
+const DEADLINE_FACTOR: u32 = 2;
+
+/// evaluation of a checkmate at the root in centipawns, a mate in `n` plies scores
+/// `MATE_SCORE - n`
+pub const MATE_SCORE: i32 = 30000;
+
+/// evaluation of a position won according to the Syzygy tables, in centipawns
+pub const TABLEBASE_SCORE: i32 = 20000;
+
+fn centipawns(evaluation: i32) -> i32 {
+    if evaluation.abs() >= MATE - MAX_MATE_PLIES {
+        evaluation.signum() * (MATE_SCORE - (MATE - evaluation.abs()))
+    } else if evaluation.abs() >= SYZYGY_WIN {
+        evaluation.signum() * TABLEBASE_SCORE
+    } else {
+        evaluation * 100
+    }
+}
+
+/// the moves until mate of a score in centipawns, negative if the side to move gets mated
+pub fn mate_in(score: i32) -> Option<i32> {
+    if score.abs() < MATE_SCORE - MAX_MATE_PLIES {
+        return None;
+    }
+    let moves = (MATE_SCORE - score.abs() + 1) / 2;
+    Some(if score > 0 { moves } else { -moves })
+}
+
+/// a line found by the search, the moves expected from the searched position and the evaluation
+/// in centipawns from the view of the side to move
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    pub moves: Vec<Move>,
+    pub score: i32,
+}
+
+impl Line {
+    /// the moves in UCI notation, separated by spaces
+    pub fn to_uci(&self, board: &Board) -> String {
+        self.notation(board, Board::move_to_uci)
+    }
+
+    /// the moves in SAN, separated by spaces
+    pub fn to_san(&self, board: &Board) -> String {
+        self.notation(board, Board::move_to_san)
+    }
+
+    fn notation(&self, board: &Board, notation: fn(&Board, &Move) -> String) -> String {
+        let mut board = board.clone();
+        let mut moves = Vec::new();
+        for _move in &self.moves {
+            moves.push(notation(&board, _move));
+            board.make_move(_move);
+        }
+        moves.join(" ")
+    }
+}
+
+/// progress of a search, reported for each line after every completed iteration
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchInfo {
+    pub depth: i32,
+    /// the most plies from the root reached
+    pub seldepth: i32,
+    /// the number of the line, 1 for the best one
+    pub multipv: usize,
+    pub line: Line,
+    /// positions searched since the start, in all iterations
+    pub nodes: u64,
+    /// nodes per second
+    pub nps: u64,
+    /// permille of the transposition table in use, 0 without one
+    pub hashfull: u32,
+    pub time: Duration,
+}
+
+/// receives the progress of a search, implemented by closures taking a `&SearchInfo`
+pub trait SearchObserver {
+    fn iteration(&mut self, info: &SearchInfo);
+}
+
+impl<F: FnMut(&SearchInfo)> SearchObserver for F {
+    fn iteration(&mut self, info: &SearchInfo) {
+        self(info)
+    }
+}
+
+/// searches the current position to a fixed depth and returns the best move found
+pub fn search(board: &mut Board, depth: i32) -> Option<Move> {
+    search_with_score(board, depth, None, None).0
+}
+
+/// the move the tables choose at the root, with the score of the position
+fn tablebase_line(
+    board: &Board,
+    tablebases: Option<&Tablebases>,
+    syzygy: Option<&Syzygy>,
+) -> Option<Line> {
+    if let Some((_move, probe)) = tablebases.and_then(|tablebases| tablebases.best_move(board)) {
+        let score = match probe {
+            Probe::Win(plies) => MATE_SCORE - plies as i32,
+            Probe::Draw => 0,
+            Probe::Loss(plies) => plies as i32 - MATE_SCORE,
+        };
+        return Some(Line {
+            moves: vec![_move],
+            score,
+        });
+    }
+    let (wdl, moves) = syzygy.and_then(|syzygy| syzygy.root_moves(board))?;
+    Some(Line {
+        moves: vec![moves.into_iter().next()?],
+        score: syzygy_evaluation(wdl).signum() * TABLEBASE_SCORE,
+    })
+}
+
+/// the first move and the score of the best line, the score of the position without moves
+fn best_move(board: &Board, lines: Vec<Line>) -> (Option<Move>, i32) {
+    match lines.into_iter().next() {
+        Some(line) => (line.moves.into_iter().next(), line.score),
+        None if board.is_check() => (None, -MATE_SCORE),
+        None => (None, 0),
+    }
+}
+
+/// like `search`, also returns the evaluation in centipawns from the view of the side to move,
+/// positions covered by `tablebases` are looked up instead of searched. At the root only moves
+/// keeping the value according to `syzygy` are played, below the tables cut off the search
+pub fn search_with_score(
+    board: &mut Board,
+    depth: i32,
+    tablebases: Option<&Tablebases>,
+    syzygy: Option<&Syzygy>,
+) -> (Option<Move>, i32) {
+    if let Some(line) = tablebase_line(board, tablebases, syzygy) {
+        return best_move(board, vec![line]);
+    }
+    let lines = Searcher::new(tablebases, syzygy).lines(board, depth, 1);
+    best_move(board, lines)
+}
+
+/// the best `count` lines of the current position, best first. Each line is searched without
+/// the first moves of the lines found before
+pub fn search_lines(
+    board: &mut Board,
+    depth: i32,
+    count: usize,
+    tablebases: Option<&Tablebases>,
+    syzygy: Option<&Syzygy>,
+) -> Vec<Line> {
+    Searcher::new(tablebases, syzygy).lines(board, depth, count)
+}
+
+/// iterative deepening up to `max_depth`, a new iteration is only started if it is expected to
+/// finish within `budget`
+pub fn search_timed(
+    board: &mut Board,
+    max_depth: i32,
+    budget: Duration,
+    tablebases: Option<&Tablebases>,
+    syzygy: Option<&Syzygy>,
+) -> (Option<Move>, i32) {
+    let mut searcher = Searcher::new(tablebases, syzygy);
+    let lines = deepen(
+        board,
+        &mut searcher,
+        max_depth,
+        Some(budget),
+        1,
+        &mut |_: &_| {},
+    );
+    best_move(board, lines)
+}
+
+/// like `search_timed` for the best `count` lines
+pub fn search_lines_timed(
+    board: &mut Board,
+    max_depth: i32,
+    budget: Duration,
+    count: usize,
+    tablebases: Option<&Tablebases>,
+    syzygy: Option<&Syzygy>,
+) -> Vec<Line> {
+    let mut searcher = Searcher::new(tablebases, syzygy);
+    deepen(
+        board,
+        &mut searcher,
+        max_depth,
+        Some(budget),
+        count,
+        &mut |_: &_| {},
+    )
+}
+
+/// how `search_observed` searches
+#[derive(Clone)]
+pub struct SearchOptions {
+    pub max_depth: i32,
+    /// a new iteration is only started if it is expected to finish within the budget, without
+    /// one all iterations up to `max_depth` are searched
+    pub budget: Option<Duration>,
+    /// the number of best lines searched
+    pub lines: usize,
+    pub tablebases: Option<Arc<Tablebases>>,
+    pub syzygy: Option<Arc<Syzygy>>,
+    /// once set the search returns the lines of the last completed iteration
+    pub stop: Option<Arc<AtomicBool>>,
+    /// threads searching in parallel, sharing a transposition table
+    pub threads: usize,
+    /// the table kept between searches, parallel searches without one use a new table
+    pub table: Option<Arc<TranspositionTable>>,
+    /// while set the search ponders, ignoring the budget. Once cleared on a ponder hit the time
+    /// since the start of the search counts towards the budget
+    pub pondering: Option<Arc<AtomicBool>>,
+    /// the search returns the lines of the last completed iteration after this many nodes
+    pub nodes: Option<u64>,
+}
+
+impl SearchOptions {
+    /// searches the best line up to `max_depth` without tables
+    pub fn new(max_depth: i32) -> SearchOptions {
+        SearchOptions {
+            max_depth,
+            budget: None,
+            lines: 1,
+            tablebases: None,
+            syzygy: None,
+            stop: None,
+            threads: 1,
+            table: None,
+            pondering: None,
+            nodes: None,
+        }
+    }
+}
+
+/// megabytes of the transposition table of a parallel search without one
+pub const DEFAULT_HASH: usize = 16;
+
+/// iterative deepening as set by `options`, reporting each iteration to `observer`. With more
+/// than one thread helpers search the same position (Lazy SMP), only filling the table the
+/// search on the calling thread reports from
+pub fn search_observed(
+    board: &mut Board,
+    options: &SearchOptions,
+    observer: &mut dyn SearchObserver,
+) -> Vec<Line> {
+    let table = match &options.table {
+        Some(table) => Some(table.clone()),
+        None if options.threads > 1 => Some(Arc::new(TranspositionTable::new(DEFAULT_HASH))),
+        None => None,
+    };
+    let done = AtomicBool::new(false);
+    let helper_nodes = AtomicU64::new(0);
+    thread::scope(|scope| {
+        for helper in 1..options.threads {
+            let mut board = board.clone();
+            let (table, done, helper_nodes) = (table.as_deref(), &done, &helper_nodes);
+            scope.spawn(move || {
+                let mut searcher =
+                    Searcher::new(options.tablebases.as_deref(), options.syzygy.as_deref());
+                searcher.table = table;
+                searcher.stop = Some(done);
+                searcher.count = Some(helper_nodes);
+                // every other helper searches one ply ahead of the main thread
+                for depth in 1 + helper as i32 % 2..=options.max_depth {
+                    searcher.lines(&mut board, depth, options.lines);
+                    if searcher.stopped {
+                        break;
+                    }
+                }
+            });
+        }
+
+        let mut searcher = Searcher::new(options.tablebases.as_deref(), options.syzygy.as_deref());
+        searcher.table = table.as_deref();
+        searcher.stop = options.stop.as_deref();
+        searcher.pondering = options.pondering.as_deref();
+        searcher.node_limit = options.nodes;
+        searcher.helper_nodes = Some(&helper_nodes);
+        let (max_depth, budget) = (options.max_depth, options.budget);
+        let lines = deepen(
+            board,
+            &mut searcher,
+            max_depth,
+            budget,
+            options.lines,
+            observer,
+        );
+        done.store(true, Ordering::Relaxed);
+        lines
+    })
+}
+
+fn deepen(
+    board: &mut Board,
+    searcher: &mut Searcher,
+    max_depth: i32,
+    budget: Option<Duration>,
+    count: usize,
+    observer: &mut dyn SearchObserver,
+) -> Vec<Line> {
+    let start = Instant::now();
+
+    if count == 1 {
+        if let Some(line) = tablebase_line(board, searcher.tablebases, searcher.syzygy) {
+            let lines = vec![line];
+            searcher.report(observer, 1, start, &lines);
+            return lines;
         }
-        return (0, None);
     }
+    // the first iteration always completes so there is a move to play
+    let (stop, node_limit) = (searcher.stop.take(), searcher.node_limit.take());
+    let mut result = Vec::new();
+    for depth in 1..=max_depth {
+        let iteration = Instant::now();
+        let lines = searcher.lines(board, depth, count);
+        if searcher.stopped {
+            break;
+        }
+        result = lines;
+        searcher.report(observer, depth, start, &result);
+        searcher.stop = stop;
+        searcher.node_limit = node_limit;
+        // an iteration taking longer than expected is cut off, move_budget leaves at least
+        // twice the budget on the clock
+        searcher.deadline = budget.map(|budget| start + budget * DEADLINE_FACTOR);
 
-    for (i, _move) in board.move_generator.moves.clone().iter().enumerate() {
-        board.make_move(&_move);
+        // each iteration takes several times as long as the previous one
+        let expected = start.elapsed() + iteration.elapsed() * BRANCHING_FACTOR;
+        let pondering = searcher
+            .pondering
+            .is_some_and(|pondering| pondering.load(Ordering::Relaxed));
+        if budget.is_some_and(|budget| expected > budget) && !pondering {
+            break;
+        }
+    }
+    result
+}
+
+/// the lines of a background search once it finished and the task waiting for them
+#[derive(Default)]
+struct Finished {
+    done: bool,
+    lines: Option<Vec<Line>>,
+    waker: Option<Waker>,
+}
 
-        let (evaluation, _) = search_moves(board, depth - 1, -beta, -alpha);
+/// a search on a worker thread with its own copy of the board, stopped when dropped
+pub struct BackgroundSearch {
+    stop: Arc<AtomicBool>,
+    pondering: Option<Arc<AtomicBool>>,
+    start: Instant,
+    finished: Arc<(Mutex<Finished>, Condvar)>,
+}
 
-        board.undo_move();
+impl BackgroundSearch {
+    /// starts searching `board`, the stop flag of `options` is replaced by the one of the search
+    pub fn start(
+        board: &Board,
+        mut options: SearchOptions,
+        mut observer: impl SearchObserver + Send + 'static,
+    ) -> BackgroundSearch {
+        let stop = Arc::new(AtomicBool::new(false));
+        options.stop = Some(stop.clone());
+        let pondering = options.pondering.clone();
+        let finished = Arc::new((Mutex::new(Finished::default()), Condvar::new()));
 
-        if -evaluation >= beta {
-            return (beta, None);
+        let mut board = board.clone();
+        let result = finished.clone();
+        thread::spawn(move || {
+            let lines = search_observed(&mut board, &options, &mut observer);
+            let (state, condvar) = &*result;
+            let mut state = state.lock().unwrap();
+            state.done = true;
+            state.lines = Some(lines);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+            condvar.notify_all();
+        });
+        BackgroundSearch {
+            stop,
+            pondering,
+            start: Instant::now(),
+            finished,
         }
-        if depth == 4 && -evaluation == 6 {
-            print!("");
+    }
+
+    /// makes the search return the lines of the last completed iteration
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// the expected move was played, from now on the search keeps to its budget. Counting from
+    /// the start of the search, the budget may be used up already
+    pub fn ponderhit(&self) {
+        if let Some(pondering) = &self.pondering {
+            pondering.store(false, Ordering::Relaxed);
         }
-        _best_move = if -evaluation > alpha { Some(_move.clone()) } else { _best_move };
-        alpha = std::cmp::max(alpha, -evaluation);
     }
-    if depth == 4 {
-        print!("");
+
+    /// the time since the search started
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished.0.lock().unwrap().done
+    }
+
+    /// the lines found if the search finished, they are only returned once
+    pub fn try_lines(&self) -> Option<Vec<Line>> {
+        self.finished.0.lock().unwrap().lines.take()
+    }
+
+    /// blocks until the search finished and returns the lines found, if not taken already
+    pub fn wait(self) -> Vec<Line> {
+        let (state, condvar) = &*self.finished;
+        let mut state = condvar
+            .wait_while(state.lock().unwrap(), |state| !state.done)
+            .unwrap();
+        state.lines.take().unwrap_or_default()
+    }
+
+    /// resolves once the search finished, the lines are then returned by `try_lines`
+    pub fn finished(&self) -> SearchFinished {
+        SearchFinished(self.finished.clone())
     }
-    (alpha, _best_move)
 }
 
-pub(crate) fn make_move(board: &mut Board) -> Move {
-    let depth = 5;
-    let now = std::time::Instant::now();
-    let (a, _move) = search_moves(board, depth, i32::MIN + 1, i32::MAX);
+impl Drop for BackgroundSearch {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
 
-    println!("Took {} µs for depth {}", now.elapsed().as_micros(), depth);
-    _move.unwrap()
+/// future of the end of a `BackgroundSearch`
+#[derive(Clone)]
+pub struct SearchFinished(Arc<(Mutex<Finished>, Condvar)>);
+
+impl SearchFinished {
+    /// a future resolved by `finish` instead of a search, ex.: for a player thinking on
+    /// another thread
+    pub(crate) fn pending() -> SearchFinished {
+        SearchFinished(Arc::new((Mutex::new(Finished::default()), Condvar::new())))
+    }
+
+    pub(crate) fn finish(&self) {
+        let (state, condvar) = &*self.0;
+        let mut state = state.lock().unwrap();
+        state.done = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        condvar.notify_all();
+    }
+}
+
+impl Future for SearchFinished {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.0 .0.lock().unwrap();
+        if state.done {
+            Poll::Ready(())
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
 }
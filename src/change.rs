@@ -1,5 +1,6 @@
 use crate::Piece;
 
+#[derive(Clone)]
 pub(crate) struct Change {
     pub(crate) start: u8,
     pub(crate) end: u8,
@@ -11,9 +12,11 @@ pub(crate) struct Change {
     pub(crate) en_passant: bool,
     pub(crate) not_able_to_castle: u8,
     pub(crate) castling: u8,
+    pub(crate) hash: u64,
 }
 
 impl Change {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         start: u8,
         end: u8,
@@ -25,6 +28,7 @@ impl Change {
         en_passant: bool,
         not_able_to_castle: u8,
         castling: u8,
+        hash: u64,
     ) -> Self {
         Self {
             start,
@@ -37,6 +41,7 @@ impl Change {
             en_passant,
             not_able_to_castle,
             castling,
+            hash,
         }
     }
 }
\ No newline at end of file
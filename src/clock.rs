@@ -0,0 +1,262 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// how the time of a move is compensated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Delay {
+    None,
+    /// Fischer increment, added after every move
+    Increment(Duration),
+    /// simple (US) delay, the clock only starts running after the delay
+    Simple(Duration),
+    /// Bronstein delay, the time used is added back, up to the delay
+    Bronstein(Duration),
+}
+
+/// one stage of a time control, ex.: 40 moves in 90 minutes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    /// moves to be made in this stage, `None` for the rest of the game
+    pub moves: Option<u32>,
+    pub time: Duration,
+    pub delay: Delay,
+}
+
+impl TimeControl {
+    pub fn sudden_death(time: Duration) -> TimeControl {
+        TimeControl {
+            moves: None,
+            time,
+            delay: Delay::None,
+        }
+    }
+}
+
+/// time left for both players, `None` if the game is played without time control
+///
+/// A clock consists of stages that are played one after another, the time of the next stage is
+/// added once the moves of the current stage are made. If the last stage has a move count it is
+/// repeated.
+#[derive(Clone, Debug, Default)]
+pub struct Clock {
+    stages: Vec<TimeControl>,
+    remaining: [Option<Duration>; 2],
+    moves: [u32; 2],
+    stage: [usize; 2],
+}
+
+impl Clock {
+    pub fn unlimited() -> Clock {
+        Clock::default()
+    }
+
+    /// both players start with `base` and get `increment` added after each move
+    pub fn new(base: Duration, increment: Duration) -> Clock {
+        Clock::with_stages(vec![TimeControl {
+            moves: None,
+            time: base,
+            delay: if increment.is_zero() {
+                Delay::None
+            } else {
+                Delay::Increment(increment)
+            },
+        }])
+    }
+
+    pub fn with_stages(stages: Vec<TimeControl>) -> Clock {
+        let remaining = stages.first().map(|stage| stage.time);
+        Clock {
+            stages,
+            remaining: [remaining; 2],
+            moves: [0; 2],
+            stage: [0; 2],
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    pub fn stages(&self) -> &[TimeControl] {
+        &self.stages
+    }
+
+    pub fn remaining(&self, white: bool) -> Option<Duration> {
+        self.remaining[!white as usize]
+    }
+
+    /// time shown on the clock of the player after thinking for `elapsed` on the current move
+    pub fn remaining_after(&self, white: bool, elapsed: Duration) -> Option<Duration> {
+        let elapsed = match self.delay(white) {
+            Delay::Simple(delay) => elapsed.saturating_sub(delay),
+            _ => elapsed,
+        };
+        self.remaining(white)
+            .map(|remaining| remaining.saturating_sub(elapsed))
+    }
+
+    pub fn delay(&self, white: bool) -> Delay {
+        self.stages
+            .get(self.stage[!white as usize])
+            .map_or(Delay::None, |stage| stage.delay)
+    }
+
+    /// the Fischer increment of the current stage of the player
+    pub fn increment(&self, white: bool) -> Duration {
+        match self.delay(white) {
+            Delay::Increment(increment) => increment,
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// moves until the next time control of the player, `None` in sudden death
+    pub fn moves_to_go(&self, white: bool) -> Option<u32> {
+        let stage = self.stages.get(self.stage[!white as usize])?;
+        stage.moves.map(|moves| moves - self.moves[!white as usize])
+    }
+
+    /// whether the player has run out of time
+    pub fn flagged(&self, white: bool) -> bool {
+        self.remaining(white) == Some(Duration::ZERO)
+    }
+
+    /// whether the player runs out of time after thinking for `elapsed` on the current move
+    pub fn flags_after(&self, white: bool, elapsed: Duration) -> bool {
+        self.remaining_after(white, elapsed) == Some(Duration::ZERO)
+    }
+
+    /// charges `elapsed` to the player after a move, returns false if their flag fell
+    pub fn punch(&mut self, white: bool, elapsed: Duration) -> bool {
+        let side = !white as usize;
+        let delay = self.delay(white);
+        let flagged = self.flags_after(white, elapsed);
+        let remaining = match &mut self.remaining[side] {
+            None => return true,
+            Some(remaining) => remaining,
+        };
+        if flagged {
+            *remaining = Duration::ZERO;
+            return false;
+        }
+
+        *remaining = match delay {
+            Delay::None => *remaining - elapsed,
+            Delay::Increment(increment) => *remaining - elapsed + increment,
+            Delay::Simple(delay) => *remaining - elapsed.saturating_sub(delay),
+            Delay::Bronstein(delay) => *remaining - elapsed + elapsed.min(delay),
+        };
+
+        // move on to the next stage once its moves are made
+        self.moves[side] += 1;
+        let stage = &self.stages[self.stage[side]];
+        if stage.moves == Some(self.moves[side]) {
+            self.moves[side] = 0;
+            self.stage[side] = (self.stage[side] + 1).min(self.stages.len() - 1);
+            *remaining += self.stages[self.stage[side]].time;
+        }
+        true
+    }
+
+    /// thinking time for the next move of the player, `None` without time control
+    pub fn move_budget(&self, white: bool) -> Option<Duration> {
+        Some(move_budget(
+            self.remaining_after(white, Duration::ZERO)?,
+            self.increment(white),
+            self.moves_to_go(white),
+        ))
+    }
+}
+
+/// thinking time for a move given the time left, the increment and the moves until the next
+/// time control
+pub fn move_budget(remaining: Duration, increment: Duration, moves_to_go: Option<u32>) -> Duration {
+    let moves_to_go = moves_to_go.unwrap_or(30).clamp(1, 30);
+    let budget = remaining / moves_to_go + increment * 3 / 4;
+    // keep a reserve to not lose on time because of overhead
+    budget.min(remaining.saturating_sub(Duration::from_millis(50)) / 2)
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else if seconds >= 20 {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        format!("0:{:02}.{}", seconds, duration.subsec_millis() / 100)
+    }
+}
+
+/// formats the time left on a clock, ex.: 4:59 or 0:09.3
+pub fn format_time(remaining: Option<Duration>) -> String {
+    remaining.map_or_else(|| String::from("-"), format_duration)
+}
+
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "White {} | Black {}",
+            format_time(self.remaining(true)),
+            format_time(self.remaining(false))
+        )
+    }
+}
+
+/// parses time controls in the format of the PGN TimeControl tag with stages separated by ':', ex.:
+/// "40/5400+30:1800+30", "300+2", "300d5" (simple delay) or "300b5" (Bronstein delay), times are
+/// given in seconds
+impl FromStr for Clock {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let seconds = |s: &str| {
+            s.parse::<f64>()
+                .ok()
+                .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                .ok_or_else(|| format!("invalid time {}", s))
+                .and_then(|seconds| {
+                    Duration::try_from_secs_f64(seconds)
+                        .map_err(|err| format!("invalid time {}: {}", s, err))
+                })
+        };
+
+        let mut stages = Vec::new();
+        for stage in s.split(':') {
+            let (moves, rest) = match stage.split_once('/') {
+                Some((moves, rest)) => (
+                    Some(
+                        moves
+                            .parse()
+                            .ok()
+                            .filter(|moves| *moves > 0)
+                            .ok_or_else(|| format!("invalid move count {}", moves))?,
+                    ),
+                    rest,
+                ),
+                None => (None, stage),
+            };
+            let (time, delay) = if let Some((time, inc)) = rest.split_once('+') {
+                (time, Delay::Increment(seconds(inc)?))
+            } else if let Some((time, delay)) = rest.split_once('d') {
+                (time, Delay::Simple(seconds(delay)?))
+            } else if let Some((time, delay)) = rest.split_once('b') {
+                (time, Delay::Bronstein(seconds(delay)?))
+            } else {
+                (rest, Delay::None)
+            };
+            stages.push(TimeControl {
+                moves,
+                time: seconds(time)?,
+                delay,
+            });
+        }
+        Ok(Clock::with_stages(stages))
+    }
+}
@@ -0,0 +1,388 @@
+use std::io;
+
+use crate::clock::Clock;
+use crate::game_result::{GameResult, Outcome, Reason};
+use crate::player::Player;
+use crate::{Board, EnginePlayer, Game, UciPlayer};
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// how to start one side of a match
+#[derive(Clone, Debug)]
+pub enum EngineConfig {
+    /// the built in engine searching to a fixed depth
+    Builtin { depth: i32 },
+    /// an external UCI executable with options set after the handshake
+    Uci {
+        path: String,
+        options: Vec<(String, String)>,
+        limits: Option<String>,
+    },
+}
+
+impl EngineConfig {
+    pub fn player(&self) -> io::Result<Box<dyn Player>> {
+        match self {
+            EngineConfig::Builtin { depth } => Ok(Box::new(EnginePlayer::new(*depth))),
+            EngineConfig::Uci {
+                path,
+                options,
+                limits,
+            } => {
+                let mut player = UciPlayer::new(path)?;
+                for (name, value) in options {
+                    player.set_option(name, value)?;
+                }
+                if let Some(limits) = limits {
+                    player.set_limits(limits);
+                }
+                Ok(Box::new(player))
+            }
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            EngineConfig::Builtin { depth } => format!("chess depth {}", depth),
+            EngineConfig::Uci { path, .. } => path.clone(),
+        }
+    }
+}
+
+/// thresholds for ending games early based on the scores reported by the engines
+#[derive(Clone, Debug)]
+pub struct Adjudication {
+    /// a player resigns if their score is below `-resign_score` for `resign_moves` moves in a row
+    pub resign_score: Option<i32>,
+    pub resign_moves: usize,
+    /// the game is drawn if both scores stay within `draw_score` for `draw_moves` moves each,
+    /// starting at ply `draw_after_ply`
+    pub draw_score: Option<i32>,
+    pub draw_moves: usize,
+    pub draw_after_ply: usize,
+    /// the game is drawn after this many plies
+    pub max_ply: Option<usize>,
+}
+
+impl Default for Adjudication {
+    fn default() -> Self {
+        Adjudication {
+            resign_score: None,
+            resign_moves: 3,
+            draw_score: None,
+            draw_moves: 8,
+            draw_after_ply: 80,
+            max_ply: Some(400),
+        }
+    }
+}
+
+/// sequential probability ratio test deciding between elo difference `elo0` (H0) and `elo1` (H1)
+#[derive(Clone, Copy, Debug)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SprtResult {
+    AcceptH0,
+    AcceptH1,
+    Continue,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64) -> Sprt {
+        Sprt {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    /// (lower, upper) bounds of the log likelihood ratio
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// log likelihood ratio of the results, using the normal approximation of the trinomial model
+    pub fn llr(&self, wins: usize, draws: usize, losses: usize) -> f64 {
+        let games = (wins + draws + losses) as f64;
+        if wins + draws + losses == 0 || (wins == 0 && losses == 0) {
+            return 0.0;
+        }
+        let score = (wins as f64 + draws as f64 / 2.0) / games;
+        let variance = (wins as f64 * (1.0 - score).powi(2)
+            + draws as f64 * (0.5 - score).powi(2)
+            + losses as f64 * score.powi(2))
+            / games;
+        if variance == 0.0 {
+            return 0.0;
+        }
+        let score0 = elo_to_score(self.elo0);
+        let score1 = elo_to_score(self.elo1);
+        (score1 - score0) * (2.0 * score - score0 - score1) * games / (2.0 * variance)
+    }
+
+    pub fn test(&self, wins: usize, draws: usize, losses: usize) -> SprtResult {
+        let llr = self.llr(wins, draws, losses);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtResult::AcceptH1
+        } else if llr <= lower {
+            SprtResult::AcceptH0
+        } else {
+            SprtResult::Continue
+        }
+    }
+}
+
+fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+fn score_to_elo(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchConfig {
+    pub engines: [EngineConfig; 2],
+    pub games: usize,
+    /// starting positions as FEN, each one is played twice with colors reversed
+    pub openings: Vec<String>,
+    pub clock: Clock,
+    pub adjudication: Adjudication,
+    pub sprt: Option<Sprt>,
+    pub event: String,
+}
+
+impl MatchConfig {
+    pub fn new(engine1: EngineConfig, engine2: EngineConfig, games: usize) -> MatchConfig {
+        MatchConfig {
+            engines: [engine1, engine2],
+            games,
+            openings: Vec::new(),
+            clock: Clock::unlimited(),
+            adjudication: Adjudication::default(),
+            sprt: None,
+            event: String::from("Engine match"),
+        }
+    }
+}
+
+/// results from the view of the first engine
+#[derive(Clone, Debug, Default)]
+pub struct MatchResult {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub pgn: Vec<String>,
+    pub sprt: Option<SprtResult>,
+}
+
+impl MatchResult {
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    /// elo difference and its 95% confidence margin, `None` if one side scored every point
+    pub fn elo(&self) -> Option<(f64, f64)> {
+        let games = self.games() as f64;
+        let score = self.score();
+        if self.games() == 0 || score <= 0.0 || score >= 1.0 {
+            return None;
+        }
+        let variance = (self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / games;
+        let margin = 1.959964 * (variance / games).sqrt();
+        let lower = score_to_elo((score - margin).max(1e-6));
+        let upper = score_to_elo((score + margin).min(1.0 - 1e-6));
+        Some((score_to_elo(score), (upper - lower) / 2.0))
+    }
+}
+
+impl std::fmt::Display for MatchResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Games: {}, W: {} D: {} L: {}",
+            self.games(),
+            self.wins,
+            self.draws,
+            self.losses
+        )?;
+        if let Some((elo, margin)) = self.elo() {
+            write!(f, ", Elo: {:.1} +/- {:.1}", elo, margin)?;
+        }
+        Ok(())
+    }
+}
+
+/// reads starting positions from FEN or EPD lines, empty lines and lines starting with '#' are
+/// skipped
+pub fn parse_openings(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 4 {
+                return None;
+            }
+            // EPD has operations instead of the move counters
+            let counters = match (fields.get(4), fields.get(5)) {
+                (Some(a), Some(b))
+                    if a.parse::<u32>().is_ok()
+                        && b.trim_end_matches(';').parse::<u32>().is_ok() =>
+                {
+                    format!("{} {}", a, b.trim_end_matches(';'))
+                }
+                _ => String::from("0 1"),
+            };
+            Some(format!("{} {}", fields[..4].join(" "), counters))
+        })
+        .collect()
+}
+
+/// plays a single game and returns its result and the PGN
+fn play_game(
+    config: &MatchConfig,
+    opening: &str,
+    engine1_white: bool,
+    round: usize,
+) -> io::Result<(GameResult, String)> {
+    let invalid = |err| {
+        let message = format!("invalid opening {}: {}", opening, err);
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    };
+    if Board::try_from_fen(opening)
+        .map_err(invalid)?
+        .legal_moves()
+        .is_empty()
+    {
+        return Err(invalid(String::from("the game is already over")));
+    }
+    let first = config.engines[0].player()?;
+    let second = config.engines[1].player()?;
+    let (white, black) = if engine1_white {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    let white_name = white.name();
+    let black_name = black.name();
+
+    let mut game = Game::from_fen(opening, white, black);
+    game.set_clock(config.clock.clone());
+    let adjudication = &config.adjudication;
+    // scores of the last moves of white and black from their own view
+    let mut scores: [Vec<i32>; 2] = [Vec::new(), Vec::new()];
+
+    let result = loop {
+        let white_turn = game.board().data.white_turn;
+        let played = game
+            .step()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+
+        if let Some(result) = game.result() {
+            break result;
+        }
+        if played.is_none() {
+            continue;
+        }
+
+        let side = &mut scores[!white_turn as usize];
+        if let Some(score) = game.player(white_turn).score() {
+            side.push(score);
+        }
+
+        let ply = game.board().record().len();
+        let drawn = |scores: &Vec<i32>, draw_score: i32| {
+            scores.len() >= adjudication.draw_moves
+                && scores[scores.len() - adjudication.draw_moves..]
+                    .iter()
+                    .all(|score| score.abs() <= draw_score)
+        };
+
+        let adjudicated = if adjudication.resign_score.is_some_and(|resign_score| {
+            side.len() >= adjudication.resign_moves
+                && side[side.len() - adjudication.resign_moves..]
+                    .iter()
+                    .all(|score| *score <= -resign_score)
+        }) {
+            Some(GameResult::win_for(!white_turn, Reason::Adjudication))
+        } else if adjudication.draw_score.is_some_and(|draw_score| {
+            ply >= adjudication.draw_after_ply && scores.iter().all(|s| drawn(s, draw_score))
+        }) || adjudication.max_ply.is_some_and(|max_ply| ply >= max_ply)
+        {
+            Some(GameResult::draw(Reason::Adjudication))
+        } else {
+            None
+        };
+
+        if let Some(result) = adjudicated {
+            game.set_result(result);
+            break result;
+        }
+    };
+
+    let round = round.to_string();
+    let tags = [
+        ("Event", config.event.as_str()),
+        ("Round", round.as_str()),
+        ("White", white_name.as_str()),
+        ("Black", black_name.as_str()),
+        ("Termination", result.pgn_termination()),
+    ];
+    let pgn = game.board().record().pgn(&tags, result.pgn_result());
+    Ok((result, pgn))
+}
+
+/// plays the match, `on_game` is called with the intermediate result after every game
+pub fn run(config: &MatchConfig, mut on_game: impl FnMut(&MatchResult)) -> io::Result<MatchResult> {
+    let start = [String::from(START_FEN)];
+    let openings = if config.openings.is_empty() {
+        &start[..]
+    } else {
+        &config.openings[..]
+    };
+
+    let mut result = MatchResult::default();
+    for i in 0..config.games {
+        let opening = &openings[(i / 2) % openings.len()];
+        let engine1_white = i % 2 == 0;
+        let (game_result, pgn) = play_game(config, opening, engine1_white, i + 1)?;
+
+        match (game_result.outcome, engine1_white) {
+            (Outcome::Draw, _) => result.draws += 1,
+            (Outcome::WhiteWins, true) | (Outcome::BlackWins, false) => result.wins += 1,
+            _ => result.losses += 1,
+        }
+        result.pgn.push(pgn);
+
+        if let Some(sprt) = config.sprt {
+            let test = sprt.test(result.wins, result.draws, result.losses);
+            result.sprt = Some(test);
+            on_game(&result);
+            if test != SprtResult::Continue {
+                break;
+            }
+        } else {
+            on_game(&result);
+        }
+    }
+    Ok(result)
+}
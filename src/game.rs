@@ -0,0 +1,199 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::clock::{self, Clock};
+use crate::game_result::{GameResult, Reason};
+use crate::player::{Player, PlayerAction};
+use crate::state::State;
+use crate::{Board, IllegalMove, Move};
+
+/// drives a game between two players
+pub struct Game {
+    board: Board,
+    players: [Box<dyn Player>; 2], // white, black
+    clock: Clock,
+    turn_start: Option<Instant>,
+    draw_offer: Option<bool>, // side which offered a draw
+}
+
+impl Game {
+    pub fn new(white: Box<dyn Player>, black: Box<dyn Player>) -> Game {
+        Game {
+            board: Board::new(),
+            players: [white, black],
+            clock: Clock::unlimited(),
+            turn_start: None,
+            draw_offer: None,
+        }
+    }
+
+    pub fn from_fen(fen: &str, white: Box<dyn Player>, black: Box<dyn Player>) -> Game {
+        let mut game = Game::new(white, black);
+        game.board.load_fen(fen);
+        game
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+        self.turn_start = None;
+    }
+
+    pub fn player(&self, white: bool) -> &dyn Player {
+        self.players[!white as usize].as_ref()
+    }
+
+    pub fn player_mut(&mut self, white: bool) -> &mut dyn Player {
+        self.players[!white as usize].as_mut()
+    }
+
+    pub fn player_to_move(&self) -> &dyn Player {
+        self.player(self.board.data.white_turn)
+    }
+
+    pub fn human_turn(&self) -> bool {
+        self.player_to_move().is_human()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.board.state.is_over()
+    }
+
+    pub fn result(&self) -> Option<GameResult> {
+        self.board.state.result()
+    }
+
+    /// ends the game with `result`, ex.: after adjudication
+    pub fn set_result(&mut self, result: GameResult) {
+        self.board.state = State::Over(result);
+        self.turn_start = None;
+        self.draw_offer = None;
+    }
+
+    /// takes back the last `plies` moves and continues the game from there, returns the number
+    /// of moves taken back. The clock keeps the time used for them
+    pub fn takeback(&mut self, plies: usize) -> usize {
+        let taken = self.board.takeback(plies);
+        self.turn_start = None;
+        self.draw_offer = None;
+        taken
+    }
+
+    /// whether the opponent of the player to move offered a draw
+    pub fn draw_offered(&self) -> bool {
+        self.draw_offer == Some(!self.board.data.white_turn)
+    }
+
+    /// time left of the player including the time spent on the current move
+    pub fn time_left(&self, white: bool) -> Option<Duration> {
+        match self.turn_start {
+            Some(turn_start) if white == self.board.data.white_turn => {
+                self.clock.remaining_after(white, turn_start.elapsed())
+            }
+            _ => self.clock.remaining(white),
+        }
+    }
+
+    /// ends the game if the player to move has run out of time, returns whether the flag fell
+    pub fn check_flag(&mut self) -> bool {
+        let white_turn = self.board.data.white_turn;
+        let elapsed = match self.turn_start {
+            Some(turn_start) if !self.is_over() => turn_start.elapsed(),
+            _ => return false,
+        };
+        if !self.clock.flags_after(white_turn, elapsed) {
+            return false;
+        }
+
+        self.clock.punch(white_turn, elapsed);
+        self.set_result(if self.board.has_mating_material(!white_turn) {
+            GameResult::win_for(!white_turn, Reason::TimeForfeit)
+        } else {
+            GameResult::draw(Reason::TimeoutVsInsufficientMaterial)
+        });
+        true
+    }
+
+    /// asks the player to move and plays the move, returns `None` if the game is over or the
+    /// player is still thinking
+    pub fn step(&mut self) -> Result<Option<Move>, IllegalMove> {
+        if self.is_over() {
+            return Ok(None);
+        }
+
+        let white_turn = self.board.data.white_turn;
+        let turn_start = *self.turn_start.get_or_insert_with(Instant::now);
+        let action = self.players[!white_turn as usize].choose_move(&self.board, &self.clock);
+
+        // the flag may have fallen before the move was made
+        if self.check_flag() {
+            return Ok(None);
+        }
+
+        let (_move, offer) = match action {
+            PlayerAction::Move(_move) => (_move, false),
+            PlayerAction::OfferDraw(_move) => (_move, true),
+            PlayerAction::Wait => return Ok(None),
+            PlayerAction::Resign => {
+                self.set_result(GameResult::win_for(!white_turn, Reason::Resignation));
+                return Ok(None);
+            }
+            PlayerAction::AcceptDraw => {
+                if self.draw_offered() {
+                    self.set_result(GameResult::draw(Reason::DrawAgreement));
+                }
+                return Ok(None);
+            }
+        };
+
+        let elapsed = turn_start.elapsed();
+        self.board.push(_move.clone())?;
+        self.clock.punch(white_turn, elapsed);
+        let index = self.board.record().len() - 1;
+        self.board.record_mut().set_time_spent(index, elapsed);
+        // the clock of the opponent starts right away
+        self.turn_start = Some(Instant::now());
+
+        // an offer is valid until the opponent moves
+        self.draw_offer = None;
+        if offer && !self.is_over() {
+            self.draw_offer = Some(white_turn);
+            if self.players[white_turn as usize].draw_offered(&self.board) {
+                self.set_result(GameResult::draw(Reason::DrawAgreement));
+            }
+        }
+        Ok(Some(_move))
+    }
+
+    /// plays until the game is over or a player waits
+    pub fn play(&mut self) -> Result<(), IllegalMove> {
+        while self.step()?.is_some() {}
+        Ok(())
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let player = |white: bool| {
+            let mut line = self.player(white).name();
+            if !self.clock.is_unlimited() {
+                line.push_str(&format!(" {}", clock::format_time(self.time_left(white))));
+            }
+            line
+        };
+        writeln!(f, "{}", player(false))?;
+        writeln!(f, "{}", self.board)?;
+        write!(f, "{}", player(true))?;
+        if self.is_over() {
+            write!(f, "\n{}", self.board.state)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+use crate::Move;
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// a move of the game together with the resulting position
+#[derive(Clone, Debug)]
+pub struct RecordEntry {
+    pub _move: Move,
+    pub san: String,
+    pub fen: String,
+    pub hash: u64,
+    pub time_spent: Option<Duration>,
+    pub comment: Option<String>,
+}
+
+impl RecordEntry {
+    pub(crate) fn new(_move: Move, san: String, fen: String, hash: u64) -> RecordEntry {
+        RecordEntry {
+            _move,
+            san,
+            fen,
+            hash,
+            time_spent: None,
+            comment: None,
+        }
+    }
+}
+
+/// all moves played on a `Board` since the starting position was loaded
+///
+/// The record is kept up to date by `Board::push` and `Board::pop`, positions of earlier plies
+/// are available through `Board::position_at` and `Board::positions`.
+#[derive(Clone, Debug)]
+pub struct GameRecord {
+    start_fen: String,
+    start_hash: u64,
+    entries: Vec<RecordEntry>,
+}
+
+impl GameRecord {
+    pub(crate) fn new(start_fen: String, start_hash: u64) -> GameRecord {
+        GameRecord {
+            start_fen,
+            start_hash,
+            entries: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, entry: RecordEntry) {
+        self.entries.push(entry);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<RecordEntry> {
+        self.entries.pop()
+    }
+
+    /// the record of the first `len` moves
+    pub(crate) fn truncated(&self, len: usize) -> GameRecord {
+        GameRecord {
+            start_fen: self.start_fen.clone(),
+            start_hash: self.start_hash,
+            entries: self.entries[..len].to_vec(),
+        }
+    }
+
+    pub fn start_fen(&self) -> &str {
+        &self.start_fen
+    }
+
+    pub fn entries(&self) -> &[RecordEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn moves(&self) -> impl Iterator<Item = &Move> + '_ {
+        self.entries.iter().map(|entry| &entry._move)
+    }
+
+    /// fen of the position after `ply` moves
+    pub fn fen_at(&self, ply: usize) -> Option<&str> {
+        match ply {
+            0 => Some(&self.start_fen),
+            _ => self.entries.get(ply - 1).map(|entry| entry.fen.as_str()),
+        }
+    }
+
+    /// sets the thinking time of the move at `index` in `entries`
+    pub fn set_time_spent(&mut self, index: usize, time_spent: Duration) {
+        if let Some(entry) = self.entries.get_mut(index) {
+            entry.time_spent = Some(time_spent);
+        }
+    }
+
+    pub fn set_comment(&mut self, index: usize, comment: String) {
+        if let Some(entry) = self.entries.get_mut(index) {
+            entry.comment = Some(comment);
+        }
+    }
+
+    /// how often the position with `hash` occurred in the game, only the positions since the
+    /// last of the `reversible` plies without a capture or pawn move can repeat
+    pub fn repetitions(&self, hash: u64, reversible: usize) -> usize {
+        let first = self.entries.len().saturating_sub(reversible + 1);
+        self.entries[first..]
+            .iter()
+            .filter(|entry| entry.hash == hash)
+            .count()
+            + (reversible >= self.entries.len() && self.start_hash == hash) as usize
+    }
+
+    /// exports the game as PGN with the seven tag roster taken from `tags`, missing tags are
+    /// filled with "?"
+    pub fn pgn(&self, tags: &[(&str, &str)], result: &str) -> String {
+        let mut pgn = String::new();
+        for name in ["Event", "Site", "Date", "Round", "White", "Black"] {
+            let value = tags
+                .iter()
+                .find(|(tag, _)| *tag == name)
+                .map_or("?", |t| t.1);
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        pgn.push_str(&format!("[Result \"{}\"]\n", result));
+        if self.start_fen != START_FEN {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{}\"]\n", self.start_fen));
+        }
+        for (name, value) in tags {
+            if !SEVEN_TAG_ROSTER.contains(name) && *name != "SetUp" && *name != "FEN" {
+                pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+            }
+        }
+        pgn.push('\n');
+
+        let fields: Vec<&str> = self.start_fen.split_whitespace().collect();
+        let mut white_turn = fields.get(1) != Some(&"b");
+        let mut move_number: usize = fields.get(5).and_then(|n| n.parse().ok()).unwrap_or(1);
+
+        let mut tokens = Vec::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            if white_turn {
+                tokens.push(format!("{}.", move_number));
+            } else if i == 0 {
+                tokens.push(format!("{}...", move_number));
+            }
+            tokens.push(entry.san.clone());
+            if let Some(comment) = &entry.comment {
+                // a comment ends at the first brace, there is no escape
+                tokens.push(format!("{{{}}}", comment.replace('}', "")));
+            }
+            if !white_turn {
+                move_number += 1;
+            }
+            white_turn = !white_turn;
+        }
+        tokens.push(result.to_string());
+
+        // lines of movetext are at most 80 characters long
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + token.len() + 1 > 80 {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        pgn
+    }
+}
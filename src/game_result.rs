@@ -0,0 +1,97 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl Outcome {
+    pub fn win_for(white: bool) -> Outcome {
+        if white {
+            Outcome::WhiteWins
+        } else {
+            Outcome::BlackWins
+        }
+    }
+}
+
+/// why the game ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reason {
+    Checkmate,
+    Stalemate,
+    Repetition,
+    FiftyMoveRule,
+    InsufficientMaterial,
+    TimeForfeit,
+    /// the flag fell but the opponent can not checkmate
+    TimeoutVsInsufficientMaterial,
+    Resignation,
+    DrawAgreement,
+    Adjudication,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameResult {
+    pub outcome: Outcome,
+    pub reason: Reason,
+}
+
+impl GameResult {
+    pub fn new(outcome: Outcome, reason: Reason) -> GameResult {
+        GameResult { outcome, reason }
+    }
+
+    pub fn draw(reason: Reason) -> GameResult {
+        GameResult::new(Outcome::Draw, reason)
+    }
+
+    pub fn win_for(white: bool, reason: Reason) -> GameResult {
+        GameResult::new(Outcome::win_for(white), reason)
+    }
+
+    /// value of the PGN Result tag
+    pub fn pgn_result(&self) -> &'static str {
+        match self.outcome {
+            Outcome::WhiteWins => "1-0",
+            Outcome::BlackWins => "0-1",
+            Outcome::Draw => "1/2-1/2",
+        }
+    }
+
+    /// value of the PGN Termination tag
+    pub fn pgn_termination(&self) -> &'static str {
+        match self.reason {
+            Reason::TimeForfeit | Reason::TimeoutVsInsufficientMaterial => "time forfeit",
+            Reason::Adjudication => "adjudication",
+            _ => "normal",
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let string = match (self.outcome, self.reason) {
+            (Outcome::Draw, Reason::Stalemate) => "Draw by stalemate",
+            (Outcome::Draw, Reason::Repetition) => "Draw by threefold repetition",
+            (Outcome::Draw, Reason::FiftyMoveRule) => "Draw due to fifty move rule",
+            (Outcome::Draw, Reason::InsufficientMaterial) => "Draw due to insufficient material",
+            (Outcome::Draw, Reason::TimeoutVsInsufficientMaterial) => {
+                "Draw by timeout vs insufficient material"
+            }
+            (Outcome::Draw, Reason::DrawAgreement) => "Draw by agreement",
+            (Outcome::Draw, _) => "Draw by adjudication",
+            (Outcome::WhiteWins, Reason::Checkmate) => "White won by checkmate",
+            (Outcome::BlackWins, Reason::Checkmate) => "Black won by checkmate",
+            (Outcome::WhiteWins, Reason::TimeForfeit) => "White won on time",
+            (Outcome::BlackWins, Reason::TimeForfeit) => "Black won on time",
+            (Outcome::WhiteWins, Reason::Resignation) => "White won by resignation",
+            (Outcome::BlackWins, Reason::Resignation) => "Black won by resignation",
+            (Outcome::WhiteWins, _) => "White won by adjudication",
+            (Outcome::BlackWins, _) => "Black won by adjudication",
+        };
+        write!(f, "{}", string)
+    }
+}
@@ -2,24 +2,52 @@
 
 pub mod _move;
 pub mod board;
+pub mod bot;
 pub mod change;
+pub mod clock;
+pub mod engine_match;
+pub mod game;
+pub mod game_result;
+pub mod game_record;
 pub mod move_generator;
+pub mod pgn;
 pub mod piece;
 pub mod piece_list;
 pub mod piece_moves;
 pub mod player;
+pub mod polyglot;
 pub mod position;
 pub mod state;
-mod bot;
+pub mod strength;
+pub mod syzygy;
+pub mod tablebase;
+pub mod transposition;
+pub mod uci;
+pub mod uci_player;
+mod zobrist;
 
-pub use _move::Move;
+pub use _move::{IllegalMove, Move};
 pub use board::Board;
+pub use game_record::GameRecord;
 pub use piece::{Piece, PieceColor, PieceType};
-pub use player::PlayerType;
+pub use clock::Clock;
+pub use game::Game;
+pub use game_result::{GameResult, Outcome, Reason};
+pub use player::{
+    BackgroundPlayer, EnginePlayer, GuiHandle, GuiPlayer, Player, PlayerAction, RandomPlayer,
+    TerminalPlayer,
+};
+pub use polyglot::Book;
+pub use strength::Strength;
+pub use uci::UciEngine;
+pub use uci_player::{UciOption, UciPlayer};
 
 #[cfg(test)]
 mod tests {
     use crate::board::PerftPositions;
+    use crate::position::Position;
+    use crate::state::State;
+    use std::time::Duration;
 
     use super::*;
 
@@ -35,14 +63,14 @@ mod tests {
     #[test]
     fn position1() {
         let results = [20, 400, 8902, 197281, 4865609, 119060324, 3195901860];
-        let mut board = Board::new(PlayerType::HumanPlayer, PlayerType::HumanPlayer);
+        let mut board = Board::new();
         run_test(board, &results, 7);
     }
 
     #[test]
     fn position2() {
         let results = [48, 2039, 97862, 4085603, 193690690, 8031647685];
-        let mut board = Board::new(PlayerType::HumanPlayer, PlayerType::HumanPlayer);
+        let mut board = Board::new();
         board.load_fen(PerftPositions::POSITION2);
         run_test(board, &results, 6);
     }
@@ -50,7 +78,7 @@ mod tests {
     #[test]
     fn position3() {
         let results = [14, 191, 2812, 43238, 674624, 11030083];
-        let mut board = Board::new(PlayerType::HumanPlayer, PlayerType::HumanPlayer);
+        let mut board = Board::new();
         board.load_fen(PerftPositions::POSITION3);
         run_test(board, &results, 6);
     }
@@ -58,7 +86,7 @@ mod tests {
     #[test]
     fn position4() {
         let results = [6, 264, 9467, 422333, 15833292, 706045033];
-        let mut board = Board::new(PlayerType::HumanPlayer, PlayerType::HumanPlayer);
+        let mut board = Board::new();
         board.load_fen(PerftPositions::POSITION4);
         run_test(board, &results, 6);
     }
@@ -66,7 +94,7 @@ mod tests {
     #[test]
     fn position5() {
         let results = [44, 1486, 62379, 2103487, 89941194];
-        let mut board = Board::new(PlayerType::HumanPlayer, PlayerType::HumanPlayer);
+        let mut board = Board::new();
         board.load_fen(PerftPositions::POSITION5);
         run_test(board, &results, 5);
     }
@@ -74,8 +102,1323 @@ mod tests {
     #[test]
     fn position6() {
         let results = [46, 2079, 89890, 3894594, 164075551, 6923051137];
-        let mut board = Board::new(PlayerType::HumanPlayer, PlayerType::HumanPlayer);
+        let mut board = Board::new();
         board.load_fen(PerftPositions::POSITION6);
         run_test(board, &results, 6);
     }
+
+    /// the positions of an EPD perft suite with the number of positions at each depth
+    fn perft_suite(path: &str) -> Vec<(Board, Vec<usize>)> {
+        let epd = std::fs::read_to_string(path).unwrap();
+        epd.lines()
+            .map(|line| {
+                let mut fields = line.split(';');
+                let board = Board::from_fen(fields.next().unwrap().trim());
+                let counts = fields
+                    .map(|field| field.split_whitespace().nth(1).unwrap().parse().unwrap())
+                    .collect();
+                (board, counts)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chess960_perft() {
+        for (board, counts) in perft_suite("tests/fixtures/chess960.epd") {
+            run_test(board, &counts, 4);
+        }
+    }
+
+    #[test]
+    #[ignore = "takes minutes, runs the depths beyond 4"]
+    fn chess960_perft_deep() {
+        for (board, counts) in perft_suite("tests/fixtures/chess960.epd") {
+            let depth = counts.len() as u8;
+            run_test(board, &counts, depth);
+        }
+    }
+
+    #[test]
+    fn chess960_castling_uci() {
+        let mut board = Board::new();
+        board.load_fen("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9");
+        assert!(board.is_chess960());
+
+        // the king castles onto the square of its own rook
+        let _move = board.move_from_uci("f1g1").unwrap();
+        assert_eq!(_move.castling(), 1);
+        assert_eq!(board.move_to_uci(&_move), "f1g1");
+
+        board.make_move(&_move);
+        assert!(board.get_piece_at_position(Position::new(0, 6)).is_king());
+        assert!(board.get_piece_at_position(Position::new(0, 5)).is_rook());
+
+        board.undo_move();
+        assert!(board.get_piece_at_position(Position::new(0, 5)).is_king());
+        assert!(board.get_piece_at_position(Position::new(0, 6)).is_rook());
+    }
+
+    #[test]
+    fn standard_castling_uci() {
+        let mut board = Board::new();
+        board.load_fen(PerftPositions::POSITION2);
+        assert!(!board.is_chess960());
+
+        let _move = board.move_from_uci("e1g1").unwrap();
+        assert_eq!(_move.castling(), 1);
+        assert_eq!(board.move_to_uci(&_move), "e1g1");
+
+        board.set_chess960(true);
+        assert_eq!(board.move_to_uci(&_move), "e1h1");
+        assert_eq!(board.move_from_uci("e1h1").unwrap().castling(), 1);
+    }
+
+    #[test]
+    fn legal_moves() {
+        let mut board = Board::new();
+        board.load_fen(PerftPositions::POSITION2);
+        assert_eq!(board.legal_moves().len(), 48);
+
+        let e2 = Position::new(1, 4);
+        assert_eq!(board.moves_from(e2).len(), 6);
+        assert!(board
+            .moves_from(Position::new(0, 4))
+            .iter()
+            .all(|m| board.is_legal(m)));
+        assert!(board.moves_from(Position::new(3, 0)).is_empty());
+
+        let _move = board.move_from_uci("e2a6").unwrap();
+        board.make_move(&_move);
+        assert_eq!(board.legal_moves().len(), 36);
+        assert!(!board.is_legal(&_move));
+    }
+
+    #[test]
+    fn checks_and_attackers() {
+        let mut board = Board::new();
+        board.load_fen("rnbqkbnr/ppppp2p/5p2/6p1/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3");
+        assert!(!board.is_check());
+
+        let mate = board.move_from_uci("d1h5").unwrap();
+        assert!(board.gives_check(&mate));
+        assert!(!board.gives_check(&board.move_from_uci("d2d4").unwrap()));
+
+        board.make_move(&mate);
+        assert!(board.is_check());
+        assert!(board.legal_moves().is_empty());
+
+        let e8 = Position::new(7, 4);
+        assert_eq!(
+            board.attackers_of(e8, PieceColor::White),
+            vec![Position::new(4, 7)]
+        );
+        assert_eq!(
+            board.attackers_of(Position::new(5, 5), PieceColor::Black),
+            vec![Position::new(6, 4), Position::new(7, 6)]
+        );
+    }
+
+    #[test]
+    fn push_and_pop() {
+        let mut board = Board::new();
+        let moves = ["f2f3", "e7e5", "g2g4", "d8h4"];
+
+        for uci in moves {
+            let _move = board.move_from_uci(uci).unwrap();
+            assert_eq!(board.push(_move), Ok(()));
+        }
+        assert_eq!(board.ply(), 4);
+        assert_eq!(
+            board.state.result(),
+            Some(GameResult::win_for(false, Reason::Checkmate))
+        );
+
+        let illegal =
+            Move::from_positions(Position::new(1, 0), Position::new(2, 0), 0, 0, false, false);
+        assert_eq!(board.push(illegal.clone()), Err(IllegalMove(illegal)));
+
+        for uci in moves.iter().rev() {
+            assert_eq!(
+                board.pop().map(|m| board.move_to_uci(&m)),
+                Some(uci.to_string())
+            );
+        }
+        assert_eq!(board.pop(), None);
+        assert_eq!(board.ply(), 0);
+        assert!(board.state == State::Playing);
+        assert_eq!(board.legal_moves().len(), 20);
+
+        let double_step =
+            Move::from_positions(Position::new(1, 4), Position::new(3, 4), 0, 0, true, false);
+        assert_eq!(board.push(double_step), Ok(()));
+        let illegal =
+            Move::from_positions(Position::new(6, 4), Position::new(3, 4), 0, 0, false, false);
+        assert!(board.push(illegal).is_err());
+        assert_eq!(board.ply(), 1);
+    }
+
+    fn check_hash(board: &mut Board, depth: u8) {
+        assert_eq!(board.hash(), zobrist::ZOBRIST.hash(&board.data));
+        if depth == 0 {
+            return;
+        }
+        for _move in board.legal_moves() {
+            board.make_move(&_move);
+            check_hash(board, depth - 1);
+            board.undo_move();
+        }
+    }
+
+    #[test]
+    fn incremental_hash() {
+        for fen in [
+            PerftPositions::POSITION2,
+            PerftPositions::POSITION3,
+            PerftPositions::POSITION4,
+            PerftPositions::POSITION5,
+            "qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9",
+        ] {
+            check_hash(&mut Board::from_fen(fen), 3);
+        }
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40",
+            "qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9",
+        ] {
+            assert_eq!(Board::from_fen(fen).fen(), fen);
+        }
+    }
+
+    #[test]
+    fn game_record() {
+        let mut board = Board::new();
+        for uci in [
+            "e2e4", "d7d5", "e4d5", "g8f6", "f1b5", "c7c6", "d5c6", "d8d2", "b1d2", "f6d5",
+        ] {
+            board.push(board.move_from_uci(uci).unwrap()).unwrap();
+        }
+        let san: Vec<&str> = board
+            .record()
+            .entries()
+            .iter()
+            .map(|e| e.san.as_str())
+            .collect();
+        assert_eq!(
+            san,
+            ["e4", "d5", "exd5", "Nf6", "Bb5+", "c6", "dxc6", "Qxd2+", "Nxd2", "Nd5"]
+        );
+        assert_eq!(
+            board.record().fen_at(3),
+            Some("rnbqkbnr/ppp1pppp/8/3P4/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2")
+        );
+
+        board
+            .record_mut()
+            .set_comment(0, String::from("best by test"));
+        assert_eq!(
+            board.record().entries()[0].comment.as_deref(),
+            Some("best by test")
+        );
+        // a brace would end the comment early
+        board.record_mut().set_comment(1, String::from("{nested}"));
+        let pgn = board.record().pgn(&[], "*");
+        assert!(pgn.contains("1. e4 {best by test} d5 {{nested} 2. exd5"), "{}", pgn);
+
+        let mut earlier = board.position_at(3);
+        assert_eq!(earlier.fen(), board.record().fen_at(3).unwrap());
+        assert_eq!(earlier.record().len(), 3);
+        assert_eq!(earlier.hash(), board.record().entries()[2].hash);
+        assert_eq!(earlier.pop(), Some(board.record().entries()[2]._move.clone()));
+        assert_eq!(earlier.fen(), board.record().fen_at(2).unwrap());
+        assert_eq!(board.positions().count(), 11);
+
+        assert_eq!(board.takeback(4), 4);
+        assert_eq!(board.fen(), board.record().fen_at(6).unwrap());
+        assert_eq!(board.hash(), board.record().entries()[5].hash);
+        assert_eq!(board.takeback(10), 6);
+        assert_eq!(board.fen(), PerftPositions::POSITION1);
+    }
+
+    #[test]
+    fn san_disambiguation() {
+        let board = Board::from_fen("r3k2r/8/8/2N1N3/8/8/8/R3K2R w KQkq - 0 1");
+        let san = |uci| board.move_to_san(&board.move_from_uci(uci).unwrap());
+        assert_eq!(san("c5d7"), "Ncd7");
+        assert_eq!(san("e1g1"), "O-O");
+        assert_eq!(san("a1a8"), "Rxa8+");
+        assert_eq!(san("a1d1"), "Rd1");
+        let board = Board::from_fen("7k/8/8/R7/8/8/8/R6K w - - 0 1");
+        assert_eq!(
+            board.move_to_san(&board.move_from_uci("a1a3").unwrap()),
+            "R1a3"
+        );
+        let board = Board::from_fen("6k1/4Pppp/8/8/8/8/8/6K1 w - - 0 1");
+        assert_eq!(
+            board.move_to_san(&board.move_from_uci("e7e8q").unwrap()),
+            "e8=Q#"
+        );
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let mut board = Board::new();
+        for _ in 0..2 {
+            for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                assert!(board.state == State::Playing);
+                board.push(board.move_from_uci(uci).unwrap()).unwrap();
+            }
+        }
+        assert_eq!(
+            board.state,
+            State::Over(GameResult::draw(Reason::Repetition))
+        );
+        board.pop();
+        assert!(board.state == State::Playing);
+        // only the positions after the last irreversible move are compared
+        assert_eq!(board.record().repetitions(board.hash(), 7), 2);
+        assert_eq!(board.record().repetitions(board.hash(), 3), 1);
+    }
+
+    #[test]
+    fn random_game() {
+        let mut game = Game::new(
+            Box::new(RandomPlayer::from_seed(1)),
+            Box::new(RandomPlayer::from_seed(2)),
+        );
+        game.play().unwrap();
+        assert!(game.is_over());
+        assert!(!game.human_turn());
+
+        let mut game = Game::from_fen(
+            "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+            Box::new(EnginePlayer::new(2)),
+            Box::new(GuiPlayer::new().0),
+        );
+        let mate = game.board().move_from_uci("a1a8");
+        assert_eq!(game.step(), Ok(mate));
+        assert_eq!(
+            game.result(),
+            Some(GameResult::win_for(true, Reason::Checkmate))
+        );
+
+        let (player, handle) = GuiPlayer::new();
+        let mut game = Game::new(Box::new(player), Box::new(RandomPlayer::from_seed(3)));
+        assert_eq!(game.step(), Ok(None));
+        handle.submit(game.board().move_from_uci("e2e4").unwrap());
+        assert!(game.step().unwrap().is_some());
+        assert!(!game.human_turn());
+        assert!(game.board().record().entries()[0].time_spent.is_some());
+
+        let mut game = Game::new(
+            Box::new(RandomPlayer::from_seed(5)),
+            Box::new(RandomPlayer::from_seed(6)),
+        );
+        game.set_clock(Clock::new(Duration::ZERO, Duration::ZERO));
+        assert_eq!(game.step(), Ok(None));
+        assert!(game.clock().flagged(true));
+        assert_eq!(
+            game.result(),
+            Some(GameResult::win_for(false, Reason::TimeForfeit))
+        );
+
+        let mut game = Game::from_fen(
+            "6k1/8/8/8/8/8/8/R5K1 w - - 0 1",
+            Box::new(RandomPlayer::from_seed(5)),
+            Box::new(RandomPlayer::from_seed(6)),
+        );
+        game.set_clock(Clock::new(Duration::ZERO, Duration::ZERO));
+        assert_eq!(game.step(), Ok(None));
+        assert_eq!(
+            game.result(),
+            Some(GameResult::draw(Reason::TimeoutVsInsufficientMaterial))
+        );
+    }
+
+    #[test]
+    fn mating_material() {
+        let material = |fen| {
+            let board = Board::from_fen(fen);
+            (board.has_mating_material(true), board.has_mating_material(false))
+        };
+        assert_eq!(material("6k1/8/8/8/8/8/8/R5K1 w - - 0 1"), (true, false));
+        assert_eq!(material("6k1/8/8/8/8/8/8/5NK1 w - - 0 1"), (false, false));
+        // the pawn or bishop can block the flight square of the king
+        assert_eq!(material("6k1/6p1/8/8/8/8/8/5NK1 w - - 0 1"), (true, true));
+        assert_eq!(material("6kb/8/8/8/8/8/8/5NK1 w - - 0 1"), (true, true));
+        assert_eq!(material("6kq/8/8/8/8/8/8/5NK1 w - - 0 1"), (false, true));
+        assert_eq!(material("6k1/8/8/8/8/8/8/4NNK1 w - - 0 1"), (true, false));
+        // bishops on squares of one color can never mate
+        assert_eq!(material("6kb/8/8/8/8/8/8/4B1K1 w - - 0 1"), (false, false));
+        assert_eq!(material("5bk1/8/8/8/8/8/8/5BK1 w - - 0 1"), (true, true));
+        assert_eq!(material("6kr/8/8/8/8/8/8/5BK1 w - - 0 1"), (true, true));
+
+        let mut board = Board::from_fen("6k1/8/8/8/8/8/8/4RBK1 b - - 0 1");
+        board.push(board.move_from_uci("g8h8").unwrap()).unwrap();
+        assert!(!board.state.is_over());
+        let mut board = Board::from_fen("7k/8/8/8/8/8/6r1/5BK1 w - - 0 1");
+        board.push(board.move_from_uci("g1g2").unwrap()).unwrap();
+        assert_eq!(
+            board.state,
+            State::Over(GameResult::draw(Reason::InsufficientMaterial))
+        );
+    }
+
+    #[test]
+    fn uci_engine() {
+        let run = |input: &str| {
+            let mut output = Vec::new();
+            UciEngine::new().run(input.as_bytes(), &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let output = run("uci\nsetoption name Depth value 2\nisready\n\
+            position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo\n");
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("id name"));
+        assert!(lines.contains(&"uciok"));
+        assert!(lines.contains(&"readyok"));
+        assert_eq!(lines.last(), Some(&"bestmove a1a8"));
+
+        let output = run("position startpos moves e2e4 e7e5 d1h5 b8c6 f1c4 g8f6\ngo depth 2\n");
+        assert_eq!(output.lines().last(), Some("bestmove h5f7"));
+
+        let output = run("position startpos moves e2e5\nposition fen xyz\n\
+            position fen 8/8/8/8/8/8/8/8 w - - 0 1\nquit\ngo\n");
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            [
+                "info string illegal move e2e5",
+                "info string invalid position: missing the side to move",
+                "info string invalid position: each side needs exactly one king",
+            ]
+        );
+
+        // a new position or search stops the running search
+        let output = run("go infinite\nposition startpos moves e2e4\ngo depth 1\n");
+        let bestmoves = output.lines().filter(|l| l.starts_with("bestmove"));
+        assert_eq!(bestmoves.count(), 2);
+    }
+
+    #[test]
+    fn engine_match() {
+        use crate::engine_match::*;
+
+        let openings = parse_openings(
+            "# comment\n\
+            rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - bm e5; id \"e4\";\n\
+            \n\
+            6k1/5ppp/8/8/8/8/8/R5K1 w - - 3 40\n",
+        );
+        assert_eq!(
+            openings,
+            [
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+                "6k1/5ppp/8/8/8/8/8/R5K1 w - - 3 40"
+            ]
+        );
+
+        let mut config = MatchConfig::new(
+            EngineConfig::Builtin { depth: 3 },
+            EngineConfig::Builtin { depth: 2 },
+            4,
+        );
+        config.openings = openings;
+        config.adjudication.max_ply = Some(20);
+        let result = run(&config, |_| {}).unwrap();
+        assert_eq!(result.games(), 4);
+        assert_eq!(result.pgn.len(), 4);
+        // white mates in one in the second opening
+        assert!(result.pgn[2].contains("40. Ra8# 1-0"));
+        assert!(result.pgn[3].contains("40. Ra8# 1-0"));
+        assert!(result.pgn[0].contains("[FEN \"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b"));
+        assert!(result.pgn[0].contains("\n1... "));
+        // an invalid position and one where black is already mated
+        for opening in ["xyz", "R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1"] {
+            config.openings = vec![String::from(opening)];
+            assert!(run(&config, |_| {}).is_err(), "{}", opening);
+        }
+
+        let sprt = Sprt::new(0.0, 10.0);
+        assert_eq!(sprt.test(10, 10, 10), SprtResult::Continue);
+        assert_eq!(sprt.test(600, 300, 400), SprtResult::AcceptH1);
+        assert_eq!(sprt.test(400, 300, 600), SprtResult::AcceptH0);
+
+        let result = MatchResult {
+            wins: 60,
+            draws: 20,
+            losses: 20,
+            ..Default::default()
+        };
+        let (elo, margin) = result.elo().unwrap();
+        assert!((elo - 147.2).abs() < 0.1);
+        assert!(margin > 50.0 && margin < 90.0);
+    }
+
+    #[test]
+    fn clock() {
+        use crate::clock::{Delay, TimeControl};
+        use std::sync::atomic::AtomicBool;
+        use std::sync::Arc;
+
+        let secs = Duration::from_secs;
+        let mut clock = Clock::new(secs(60), secs(2));
+        assert!(clock.punch(true, secs(10)));
+        assert_eq!(clock.remaining(true), Some(secs(52)));
+        assert_eq!(clock.remaining(false), Some(secs(60)));
+        assert!(!clock.punch(false, secs(60)));
+        assert!(clock.flagged(false));
+
+        let mut clock: Clock = "2/60:30d5".parse().unwrap();
+        assert_eq!(clock.moves_to_go(true), Some(2));
+        clock.punch(true, secs(20));
+        clock.punch(true, secs(20));
+        // the second stage adds its time after two moves
+        assert_eq!(clock.remaining(true), Some(secs(50)));
+        assert_eq!(clock.delay(true), Delay::Simple(secs(5)));
+        clock.punch(true, secs(3));
+        assert_eq!(clock.remaining(true), Some(secs(50)));
+        clock.punch(true, secs(10));
+        assert_eq!(clock.remaining(true), Some(secs(45)));
+        assert_eq!(clock.remaining_after(true, secs(49)), Some(secs(1)));
+        assert!(clock.flags_after(true, secs(50)));
+
+        let mut clock = Clock::with_stages(vec![TimeControl {
+            moves: None,
+            time: secs(10),
+            delay: Delay::Bronstein(secs(3)),
+        }]);
+        clock.punch(false, secs(2));
+        assert_eq!(clock.remaining(false), Some(secs(10)));
+        clock.punch(false, secs(5));
+        assert_eq!(clock.remaining(false), Some(secs(8)));
+        assert!(!clock.punch(false, secs(8)));
+
+        assert_eq!(
+            "40/5400+30:1800+30".parse::<Clock>().unwrap().stages(),
+            [
+                TimeControl {
+                    moves: Some(40),
+                    time: secs(5400),
+                    delay: Delay::Increment(secs(30)),
+                },
+                TimeControl {
+                    moves: None,
+                    time: secs(1800),
+                    delay: Delay::Increment(secs(30)),
+                }
+            ]
+        );
+        assert!("abc".parse::<Clock>().is_err());
+        assert!("0/60".parse::<Clock>().is_err());
+        for invalid in ["inf", "NaN", "1e30", "-5", "60+inf"] {
+            assert!(invalid.parse::<Clock>().is_err(), "{}", invalid);
+        }
+        assert_eq!(clock::format_time(Some(Duration::from_millis(9300))), "0:09.3");
+        assert_eq!(clock::format_time(Some(secs(299))), "4:59");
+        assert!(Clock::new(secs(300), Duration::ZERO).move_budget(true) < Some(secs(11)));
+
+        // once the budget is used up the search stops within the iteration, here after
+        // pondering past the budget
+        let options = bot::SearchOptions {
+            budget: Some(Duration::from_millis(20)),
+            pondering: Some(Arc::new(AtomicBool::new(true))),
+            ..bot::SearchOptions::new(bot::MAX_DEPTH)
+        };
+        let board = Board::from_fen(PerftPositions::POSITION2);
+        let search = bot::BackgroundSearch::start(&board, options, |_: &_| {});
+        std::thread::sleep(Duration::from_millis(500));
+        search.ponderhit();
+        let hit = std::time::Instant::now();
+        assert!(!search.wait().is_empty());
+        assert!(hit.elapsed() < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn resign_and_draw_offers() {
+        let (white, white_handle) = GuiPlayer::new();
+        let (black, black_handle) = GuiPlayer::new();
+        let mut game = Game::new(Box::new(white), Box::new(black));
+
+        let e4 = game.board().move_from_uci("e2e4").unwrap();
+        white_handle.submit_action(PlayerAction::OfferDraw(e4));
+        assert!(game.step().unwrap().is_some());
+        assert!(game.draw_offered());
+
+        // the offer expires once the opponent moves
+        black_handle.submit(game.board().move_from_uci("e7e5").unwrap());
+        game.step().unwrap();
+        assert!(!game.draw_offered());
+        white_handle.submit_action(PlayerAction::AcceptDraw);
+        assert_eq!(game.step(), Ok(None));
+        assert!(!game.is_over());
+
+        white_handle.submit_action(PlayerAction::OfferDraw(
+            game.board().move_from_uci("g1f3").unwrap(),
+        ));
+        game.step().unwrap();
+        black_handle.submit_action(PlayerAction::AcceptDraw);
+        game.step().unwrap();
+        let result = game.result().unwrap();
+        assert_eq!(result, GameResult::draw(Reason::DrawAgreement));
+        assert_eq!(result.pgn_result(), "1/2-1/2");
+        assert_eq!(result.to_string(), "Draw by agreement");
+
+        game.set_result(GameResult::win_for(true, Reason::Adjudication));
+        assert_eq!(game.result().unwrap().pgn_termination(), "adjudication");
+
+        let (white, white_handle) = GuiPlayer::new();
+        let mut game = Game::new(Box::new(white), Box::new(GuiPlayer::new().0));
+        white_handle.submit_action(PlayerAction::Resign);
+        game.step().unwrap();
+        assert_eq!(game.result(), Some(GameResult::win_for(false, Reason::Resignation)));
+        assert_eq!(game.result().unwrap().pgn_result(), "0-1");
+        assert_eq!(game.board().state.to_string(), "Black won by resignation");
+    }
+
+    #[test]
+    fn game_takeback() {
+        let (white, white_handle) = GuiPlayer::new();
+        let (black, black_handle) = GuiPlayer::new();
+        let mut game = Game::new(Box::new(white), Box::new(black));
+        for (i, uci) in ["f2f3", "e7e5", "g2g4", "d8h4"].iter().enumerate() {
+            let handle = if i % 2 == 0 { &white_handle } else { &black_handle };
+            handle.submit(game.board().move_from_uci(uci).unwrap());
+            game.step().unwrap();
+        }
+        assert_eq!(game.result(), Some(GameResult::win_for(false, Reason::Checkmate)));
+        let after_f3 = game.board().position_at(2).fen();
+
+        // taking back the mate and the move before continues the game
+        assert_eq!(game.takeback(2), 2);
+        assert!(!game.is_over());
+        assert_eq!(game.board().record().len(), 2);
+        assert_eq!(game.board().fen(), after_f3);
+        assert!(game.board().white_turn());
+        white_handle.submit(game.board().move_from_uci("g2g3").unwrap());
+        assert!(game.step().unwrap().is_some());
+
+        assert_eq!(game.takeback(5), 3);
+        assert_eq!(game.board().fen(), Board::default().fen());
+    }
+
+    #[test]
+    fn polyglot_keys() {
+        // test positions from the specification of the book format
+        let keys = [
+            ("", 0x463b96181691fc9c),
+            ("e2e4", 0x823c9b50fd114196),
+            ("e2e4 d7d5", 0x0756b94461c50fb0),
+            ("e2e4 d7d5 e4e5", 0x662fafb965db29d4),
+            ("e2e4 d7d5 e4e5 f7f5", 0x22a48b5a8e47ff78),
+            ("e2e4 d7d5 e4e5 f7f5 e1e2", 0x652a607ca3f242c1),
+            ("e2e4 d7d5 e4e5 f7f5 e1e2 e8f7", 0x00fdd303c946bdd9),
+            ("a2a4 b7b5 h2h4 b5b4 c2c4", 0x3c8123ea7b067637),
+            ("a2a4 b7b5 h2h4 b5b4 c2c4 b4c3 a1a3", 0x5c3f9b829b279560),
+        ];
+        for (moves, key) in keys {
+            let mut board = Board::new();
+            for uci in moves.split_whitespace() {
+                board.push(board.move_from_uci(uci).unwrap()).unwrap();
+            }
+            assert_eq!(polyglot::key(&board), key, "{}", moves);
+        }
+    }
+
+    #[test]
+    fn polyglot_book() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut book = Book::open("tests/fixtures/book.bin").unwrap();
+        assert_eq!(book.len(), 7);
+        assert_eq!(Book::from_bytes(&book.to_bytes()).unwrap().len(), 7);
+        assert!(Book::from_bytes(&[0; 15]).is_err());
+
+        // the illegal e2e5 of the book is skipped
+        let mut board = Board::new();
+        assert_eq!(book.entries(&board).len(), 3);
+        let moves: Vec<(String, u16)> = book
+            .moves(&board)
+            .iter()
+            .map(|(_move, weight)| (board.move_to_uci(_move), *weight))
+            .collect();
+        assert_eq!(moves.len(), 2);
+        assert!(moves.contains(&(String::from("e2e4"), 3)));
+        assert!(moves.contains(&(String::from("d2d4"), 1)));
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let e4 = board.move_from_uci("e2e4").unwrap();
+        let chosen = (0..400)
+            .filter(|_| book.choose_move(&board, &mut rng) == Some(e4.clone()))
+            .count();
+        assert!((250..350).contains(&chosen), "{}", chosen);
+
+        // castling is stored as king takes rook
+        let board =
+            Board::from_fen("r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4");
+        let castling = board.move_from_uci("e1g1").unwrap();
+        assert_eq!(book.choose_move(&board, &mut rng), Some(castling.clone()));
+        assert_eq!(polyglot::encode_move(&board, &castling), 0x107);
+
+        let board = Board::from_fen("8/P6k/8/8/8/8/8/K7 w - - 0 1");
+        let promotion = book.choose_move(&board, &mut rng).unwrap();
+        assert_eq!(board.move_to_uci(&promotion), "a7a8q");
+        let encoded = polyglot::encode_move(&board, &promotion);
+        assert_eq!(encoded, 0x4c38);
+        assert_eq!(polyglot::decode_move(&board, encoded), Some(promotion));
+
+        let mut board = Board::new();
+        board.push(e4).unwrap();
+        assert!(book.choose_move(&board, &mut rng).is_some());
+        book.set_max_ply(Some(1));
+        assert_eq!(book.choose_move(&board, &mut rng), None);
+        board.push(board.move_from_uci("e7e5").unwrap()).unwrap();
+        book.set_max_ply(None);
+        assert_eq!(book.choose_move(&board, &mut rng), None);
+
+        let input = "setoption name BookFile value tests/fixtures/book.bin\n\
+            position startpos\ngo depth 1\nsetoption name BookFile value missing.bin\n";
+        let mut output = Vec::new();
+        UciEngine::new().run(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(["bestmove e2e4", "bestmove d2d4"].contains(&lines[0]));
+        assert!(lines[1].starts_with("info string unable to read book missing.bin"));
+
+        // pondering does not answer from the book before the ponder hit
+        let input = "setoption name BookFile value tests/fixtures/book.bin\n\
+            position startpos\ngo ponder depth 1\nisready\nstop\n";
+        let mut output = Vec::new();
+        UciEngine::new().run(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        let ready = lines.iter().position(|line| *line == "readyok").unwrap();
+        assert!(lines[ready + 1..].iter().any(|line| line.starts_with("bestmove ")));
+        assert!(!lines[..ready].iter().any(|line| line.starts_with("bestmove ")));
+    }
+
+    #[test]
+    fn pgn_games() {
+        let text = "[Event \"Casual \\\"game\\\"\"]\n[Result \"1-0\"]\n\n\
+            1. e4 {best by test} e5 2.Nf3 (2. f4 exf4) Nc6 3. Bb5 $1 a6?! ; comment\n\
+            4. O-O 1-0\n\n\
+            [FEN \"8/P6k/8/8/8/8/8/K7 w - - 0 1\"]\n\n1. a8=Q+ Kg6 *\n\
+            1. d4 d5";
+        let games = pgn::parse_games(text);
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[0].tag("Event"), Some("Casual \"game\""));
+        assert_eq!(games[0].moves, ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6?!", "O-O"]);
+        assert_eq!(games[0].result, "1-0");
+        assert_eq!(games[1].result, "*");
+        assert_eq!(games[2].result, "*");
+
+        let board = games[0].board(usize::MAX).unwrap();
+        assert_eq!(board.record().entries().last().unwrap().san, "O-O");
+        assert_eq!(games[0].board(2).unwrap().record().len(), 2);
+        let board = games[1].board(usize::MAX).unwrap();
+        assert_eq!(board.fen(), "Q7/8/6k1/8/8/8/8/K7 w - - 1 2");
+        let game = &pgn::parse_games("[FEN \"8/8/8 w - - 0 1\"]\n\n1. e4 *")[0];
+        assert_eq!(
+            game.board(usize::MAX).unwrap_err(),
+            "invalid FEN 8/8/8 w - - 0 1: the board needs 8 ranks"
+        );
+
+        let board = Board::new();
+        assert_eq!(board.move_from_san("Nf3"), board.move_from_uci("g1f3"));
+        assert_eq!(board.move_from_san("Nf3!"), board.move_from_uci("g1f3"));
+        assert_eq!(board.move_from_san("Ng3"), None);
+    }
+
+    #[test]
+    fn book_builder() {
+        let text = "1. e4 e5 2. Nf3 1-0\n1. e4 e5 2. Nf3 0-1\n1. e4 c5 1-0\n1. d4 d5 0-1\n\
+            1. e4 e5 2. Nc3 1/2-1/2\n1. e4 Nf6 *";
+        let mut builder = polyglot::BookBuilder::new(3);
+        for game in pgn::parse_games(text) {
+            builder.add_pgn_game(&game).unwrap();
+        }
+        let mut board = Board::new();
+        let e4 = board.move_from_uci("e2e4").unwrap();
+        let stats = builder.stats(&board, &e4).unwrap();
+        assert_eq!((stats.count, stats.wins, stats.draws, stats.losses), (5, 2, 1, 1));
+        assert_eq!(stats.score(), Some(0.625));
+
+        // weights are two points for a win and one for a draw or an unfinished game
+        let book = builder.build();
+        let weight = |book: &Book, board: &Board, uci: &str| {
+            let _move = board.move_from_uci(uci).unwrap();
+            book.moves(board)
+                .into_iter()
+                .find(|(m, _)| *m == _move)
+                .map(|(_, weight)| weight)
+        };
+        assert_eq!(weight(&book, &board, "e2e4"), Some(6));
+        assert_eq!(weight(&book, &board, "d2d4"), None);
+        board.push(e4).unwrap();
+        assert_eq!(weight(&book, &board, "e7e5"), Some(3));
+        assert_eq!(weight(&book, &board, "g8f6"), Some(1));
+        board.push(board.move_from_uci("e7e5").unwrap()).unwrap();
+        assert_eq!(weight(&book, &board, "g1f3"), Some(2));
+        board.push(board.move_from_uci("g1f3").unwrap()).unwrap();
+        // only the first three plies are added
+        assert!(book.moves(&board).is_empty());
+
+        builder.min_count = 2;
+        builder.min_score = 0.5;
+        let book = builder.build();
+        let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+        // e5 scored 0.5 for black, Nf6 and c5 were played once
+        assert_eq!(weight(&book, &board, "e7e5"), Some(3));
+        assert_eq!(book.moves(&board).len(), 1);
+        assert_eq!(weight(&book, &Board::new(), "e2e4"), Some(6));
+        assert_eq!(Book::from_bytes(&book.to_bytes()).unwrap().len(), book.len());
+    }
+
+    /// the generated tables of KPvK and the ones it depends on, shared as generating is slow
+    fn generated_tablebases() -> &'static std::sync::Arc<tablebase::Tablebases> {
+        static TABLEBASES: std::sync::OnceLock<std::sync::Arc<tablebase::Tablebases>> =
+            std::sync::OnceLock::new();
+        TABLEBASES.get_or_init(|| {
+            let mut tablebases = tablebase::Tablebases::new();
+            tablebases.generate("KPvK").unwrap();
+            std::sync::Arc::new(tablebases)
+        })
+    }
+
+    /// the FEN of a position without castling rights given as the pieces with their squares
+    fn fen_of(pieces: &[(i8, u8)], white_turn: bool) -> String {
+        let mut fen = String::new();
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match pieces.iter().find(|(_, square)| *square == rank * 8 + file) {
+                    Some((piece, _)) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        let c = b" PBNRQK"[piece.unsigned_abs() as usize] as char;
+                        fen.push(if *piece > 0 { c } else { c.to_ascii_lowercase() });
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+        fen + if white_turn { " w - - 0 1" } else { " b - - 0 1" }
+    }
+
+    #[test]
+    fn tablebase() {
+        use std::sync::Arc;
+        use tablebase::{Probe, Tablebases};
+
+        let tablebases = generated_tablebases();
+        let mut materials: Vec<&str> = tablebases.materials().collect();
+        materials.sort();
+        assert_eq!(materials, ["KPvK", "KQvK", "KRvK"]);
+        assert!(Tablebases::new().generate("KvKP").is_err());
+
+        let probe = |fen: &str| tablebases.probe(&Board::from_fen(fen));
+        assert_eq!(probe("7k/6Q1/5K2/8/8/8/8/8 b - - 0 1"), Some(Probe::Loss(0)));
+        assert_eq!(probe("7k/8/5K2/8/8/8/8/6Q1 w - - 0 1"), Some(Probe::Win(1)));
+        assert_eq!(probe("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), Some(Probe::Draw));
+        assert_eq!(probe("8/8/8/3k4/8/8/8/R3K3 w - - 0 1"), Some(Probe::Win(27)));
+        // the king in front of the pawn wins, behind it only draws
+        assert_eq!(probe("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"), Some(Probe::Win(21)));
+        assert_eq!(probe("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"), Some(Probe::Loss(24)));
+        assert_eq!(probe("4k3/8/8/4P3/4K3/8/8/8 w - - 0 1"), Some(Probe::Draw));
+        assert_eq!(probe("7k/8/6K1/7P/8/8/8/8 w - - 0 1"), Some(Probe::Draw));
+        // black pawns are looked up with the colors swapped
+        assert_eq!(probe("8/8/8/8/4p3/4k3/8/4K3 b - - 0 1"), Some(Probe::Win(21)));
+        assert_eq!(probe("8/8/8/8/8/8/8/R3K2k w Q - 0 1"), None);
+        assert_eq!(probe("4k3/8/8/8/8/8/8/R3K1NR w - - 0 1"), None);
+
+        let board = Board::from_fen("7k/8/5K2/8/8/8/8/6Q1 w - - 0 1");
+        let (_move, value) = tablebases.best_move(&board).unwrap();
+        assert_eq!((board.move_to_uci(&_move).as_str(), value), ("g1g7", Probe::Win(1)));
+
+        // the search takes the fastest win and reports it as a mate score
+        let mut board = Board::from_fen("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1");
+        let (_move, score) = bot::search_with_score(&mut board, 1, Some(&**tablebases), None);
+        assert_eq!(score, bot::MATE_SCORE - 21);
+        let mut child = board.clone();
+        child.push(_move.unwrap()).unwrap();
+        assert_eq!(tablebases.probe(&child), Some(Probe::Loss(20)));
+
+        let dir = std::env::temp_dir().join(format!("chess-tablebase-{}", std::process::id()));
+        tablebases.save(&dir).unwrap();
+        let tablebases = Arc::new(Tablebases::load(&dir).unwrap());
+        assert_eq!(tablebases.max_pieces(), 3);
+        let board = Board::from_fen("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1");
+        assert_eq!(tablebases.probe(&board), Some(Probe::Win(21)));
+        std::fs::write(dir.join("KQvK.tb"), [0; 16]).unwrap();
+        assert!(Tablebases::load(&dir).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let mut game = Game::from_fen(
+            "8/8/8/3k4/8/8/8/R3K3 w - - 0 1",
+            Box::new(EnginePlayer::new(1).with_tablebases(tablebases.clone())),
+            Box::new(EnginePlayer::new(1).with_tablebases(tablebases)),
+        );
+        game.play().unwrap();
+        assert_eq!(game.result(), Some(GameResult::win_for(true, Reason::Checkmate)));
+        assert_eq!(game.board().record().len(), 27);
+    }
+
+    #[test]
+    fn syzygy() {
+        use syzygy::{Syzygy, Wdl};
+        use tablebase::Probe;
+
+        // tables written from the generated ones, the plies to zeroing are the plies to mate
+        // without captures or pawns
+        let tablebases = generated_tablebases();
+        let wdl = |probe| match probe {
+            Probe::Win(_) => Wdl::Win,
+            Probe::Draw => Wdl::Draw,
+            Probe::Loss(_) => Wdl::Loss,
+        };
+        let dir = std::env::temp_dir().join(format!("chess-syzygy-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for material in ["KQvK", "KRvK", "KPvK"] {
+            let positions = tablebases
+                .positions(material)
+                .map(|(pieces, white_turn, probe)| (pieces, white_turn, wdl(probe) as u16));
+            let path = dir.join(format!("{}.rtbw", material));
+            syzygy::write_table(path, material, false, positions).unwrap();
+        }
+        for material in ["KQvK", "KRvK"] {
+            let positions = tablebases.positions(material).filter(|(_, white_turn, _)| *white_turn);
+            let positions = positions.map(|(pieces, white_turn, probe)| match probe {
+                Probe::Win(plies) => (pieces, white_turn, plies as u16 - 1),
+                _ => (pieces, white_turn, 0),
+            });
+            let path = dir.join(format!("{}.rtbz", material));
+            syzygy::write_table(path, material, true, positions).unwrap();
+        }
+        let syzygy = Syzygy::load(&dir).unwrap();
+        assert_eq!(syzygy.max_pieces(), 3);
+
+        for material in ["KQvK", "KRvK", "KPvK"] {
+            for (pieces, white_turn, probe) in tablebases.positions(material).step_by(211) {
+                let fen = fen_of(&pieces, white_turn);
+                let board = Board::from_fen(&fen);
+                assert_eq!(syzygy.probe_wdl(&board), Some(wdl(probe)), "{}", fen);
+                let swapped: Vec<(i8, u8)> =
+                    pieces.iter().map(|(piece, square)| (-piece, square ^ 56)).collect();
+                let swapped = Board::from_fen(&fen_of(&swapped, !white_turn));
+                assert_eq!(syzygy.probe_wdl(&swapped), Some(wdl(probe)), "{}", fen);
+                if material != "KPvK" {
+                    let dtz = match probe {
+                        Probe::Win(plies) => plies as i32,
+                        Probe::Draw => 0,
+                        Probe::Loss(plies) => -(plies as i32).max(1),
+                    };
+                    assert_eq!(syzygy.probe_dtz(&board), Some(dtz), "{}", fen);
+                }
+            }
+        }
+
+        // only winning moves are kept, the fastest first
+        let board = Board::from_fen("8/8/8/3k4/8/8/8/R3K3 w - - 0 1");
+        let (value, moves) = syzygy.root_moves(&board).unwrap();
+        assert_eq!(value, Wdl::Win);
+        for (i, _move) in moves.iter().enumerate() {
+            let mut child = board.clone();
+            child.push(_move.clone()).unwrap();
+            let probe = tablebases.probe(&child).unwrap();
+            assert!(matches!(probe, Probe::Loss(_)));
+            assert!(i > 0 || probe == Probe::Loss(26));
+        }
+        // without a DTZ table the search cuts off at the WDL values
+        let mut board = Board::from_fen("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1");
+        assert!(syzygy.root_moves(&board).is_none());
+        let (_move, score) = bot::search_with_score(&mut board, 1, None, Some(&syzygy));
+        assert_eq!(score, bot::TABLEBASE_SCORE);
+        board.push(_move.unwrap()).unwrap();
+        assert!(matches!(tablebases.probe(&board), Some(Probe::Loss(_))));
+
+        assert_eq!(syzygy.probe_wdl(&Board::from_fen("8/8/8/8/8/8/8/R3K2k w Q - 0 1")), None);
+        assert_eq!(syzygy.probe_wdl(&Board::from_fen("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1")), None);
+        std::fs::write(dir.join("KNvK.rtbw"), [0; 16]).unwrap();
+        assert!(Syzygy::load(&dir).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn syzygy_published_tables() {
+        use syzygy::{Syzygy, Wdl};
+        use tablebase::Probe;
+
+        let dir = std::path::Path::new("tests/fixtures/syzygy");
+        let missing: Vec<String> = ["KQvK", "KRvK", "KPvK"]
+            .iter()
+            .flat_map(|material| ["rtbw", "rtbz"].map(|ext| format!("{}.{}", material, ext)))
+            .filter(|file| !dir.join(file).exists())
+            .collect();
+        if !missing.is_empty() {
+            eprintln!("skipping, {} missing in {}", missing.join(" "), dir.display());
+            return;
+        }
+
+        let syzygy = Syzygy::load(dir).unwrap();
+        let board = Board::from_fen("7k/8/5K2/8/8/8/8/6Q1 w - - 0 1");
+        assert_eq!(syzygy.probe_wdl(&board), Some(Wdl::Win));
+        assert_eq!(syzygy.probe_dtz(&board), Some(1));
+        // the king takes the rook
+        let board = Board::from_fen("8/8/8/8/8/8/8/Rk2K3 b - - 0 1");
+        assert_eq!(syzygy.probe_wdl(&board), Some(Wdl::Draw));
+        assert_eq!(syzygy.probe_dtz(&board), Some(0));
+        // stalemate, and the king in front of the pawn with the opposition
+        let board = Board::from_fen("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1");
+        assert_eq!(syzygy.probe_wdl(&board), Some(Wdl::Draw));
+        let board = Board::from_fen("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1");
+        assert_eq!(syzygy.probe_wdl(&board), Some(Wdl::Loss));
+        assert!(syzygy.probe_dtz(&board).is_some_and(|dtz| dtz < 0));
+
+        // without pawns and captures the plies to zeroing are the plies to mate
+        let tablebases = generated_tablebases();
+        for material in ["KQvK", "KRvK"] {
+            for (pieces, white_turn, probe) in tablebases.positions(material).step_by(97) {
+                let board = Board::from_fen(&fen_of(&pieces, white_turn));
+                let (wdl, dtz) = match probe {
+                    Probe::Win(plies) => (Wdl::Win, plies as i32),
+                    Probe::Draw => (Wdl::Draw, 0),
+                    Probe::Loss(plies) => (Wdl::Loss, -(plies as i32).max(1)),
+                };
+                assert_eq!(syzygy.probe_wdl(&board), Some(wdl), "{}", board.fen());
+                assert_eq!(syzygy.probe_dtz(&board), Some(dtz), "{}", board.fen());
+            }
+        }
+    }
+
+    #[test]
+    fn multi_pv() {
+        let mut board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let lines = bot::search_lines(&mut board, 2, 3, None, None);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].to_san(&board), "Ra8#");
+        assert_eq!(lines[0].score, bot::MATE_SCORE - 1);
+        assert_eq!(bot::mate_in(lines[0].score), Some(1));
+        assert_eq!(bot::mate_in(2 - bot::MATE_SCORE), Some(-1));
+        assert_eq!(bot::mate_in(bot::TABLEBASE_SCORE), None);
+        assert!(lines.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        assert!(lines.windows(2).all(|pair| pair[0].moves[0] != pair[1].moves[0]));
+        let moves = board.legal_moves().len();
+        assert_eq!(bot::search_lines(&mut board, 2, 100, None, None).len(), moves);
+
+        // the principal variation reaches the depth searched
+        let lines = bot::search_lines(&mut Board::new(), 3, 1, None, None);
+        assert_eq!(lines[0].moves.len(), 3);
+
+        let input = "setoption name MultiPV value 2\n\
+            position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo depth 2\n";
+        let mut output = Vec::new();
+        UciEngine::new().run(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[2].starts_with("info depth 2 seldepth 2 multipv 1 score mate 1 nodes "));
+        assert!(lines[2].ends_with(" pv a1a8"));
+        assert!(lines[3].starts_with("info depth 2 seldepth 2 multipv 2 score cp "));
+        assert_eq!(lines[4], "bestmove a1a8");
+    }
+
+    #[test]
+    fn search_observer() {
+        let mut infos = Vec::new();
+        let mut board = Board::new();
+        let options = bot::SearchOptions {
+            lines: 2,
+            ..bot::SearchOptions::new(3)
+        };
+        let lines = bot::search_observed(&mut board, &options, &mut |info: &_| {
+            infos.push(bot::SearchInfo::clone(info))
+        });
+        let iterations: Vec<(i32, usize)> = infos.iter().map(|i| (i.depth, i.multipv)).collect();
+        assert_eq!(iterations, [(1, 1), (1, 2), (2, 1), (2, 2), (3, 1), (3, 2)]);
+        assert!(infos.windows(2).all(|pair| pair[0].nodes <= pair[1].nodes));
+        assert!(infos.iter().all(|info| info.seldepth == info.depth));
+        assert!(infos.iter().all(|info| info.line.moves.len() == info.depth as usize));
+        assert_eq!(lines, [infos[4].line.clone(), infos[5].line.clone()]);
+        assert_eq!(board.fen(), Board::new().fen());
+
+        // the tables answer without searching
+        let mut infos = Vec::new();
+        let tablebases = generated_tablebases();
+        let mut board = Board::from_fen("7k/8/5K2/8/8/8/8/6Q1 w - - 0 1");
+        let options = bot::SearchOptions {
+            tablebases: Some(tablebases.clone()),
+            ..bot::SearchOptions::new(3)
+        };
+        bot::search_observed(&mut board, &options, &mut |info: &_| {
+            infos.push(bot::SearchInfo::clone(info))
+        });
+        assert_eq!(infos.len(), 1);
+        assert_eq!((infos[0].nodes, infos[0].line.score), (0, bot::MATE_SCORE - 1));
+    }
+
+    #[test]
+    fn background_search() {
+        use bot::{BackgroundSearch, SearchOptions};
+
+        // an unlimited search ends when stopped, with the lines of the last full iteration
+        let board = Board::new();
+        let options = SearchOptions::new(bot::MAX_DEPTH);
+        let search = BackgroundSearch::start(&board, options, |_: &_| {});
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!search.is_finished());
+        search.stop();
+        let lines = search.wait();
+        assert_eq!(lines.len(), 1);
+        assert!(board.legal_moves().contains(&lines[0].moves[0]));
+
+        let search = BackgroundSearch::start(&board, SearchOptions::new(1), |_: &_| {});
+        while !search.is_finished() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(search.try_lines().map(|lines| lines.len()), Some(1));
+        assert_eq!(search.try_lines(), None);
+
+        // a background engine waits until its search finished
+        let mut game = Game::new(
+            Box::new(EnginePlayer::new(1).in_background()),
+            Box::new(EnginePlayer::new(1)),
+        );
+        assert!(matches!(game.step(), Ok(None)));
+        assert!(game.player_to_move().thinking().is_some());
+        while let Ok(None) = game.step() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_ne!(game.board().fen(), board.fen());
+
+        // `stop` ends `go infinite` with a best move
+        let mut output = Vec::new();
+        uci::UciEngine::new()
+            .run("position startpos\ngo infinite\nstop\n".as_bytes(), &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.lines().last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn lazy_smp() {
+        use bot::SearchOptions;
+        use std::sync::Arc;
+        use transposition::{Bound, Entry, TranspositionTable};
+
+        let table = TranspositionTable::new(1);
+        let board = Board::from_fen(board::PerftPositions::POSITION5);
+        let entry = Entry {
+            depth: 7,
+            score: -123,
+            bound: Bound::Lower,
+            best_move: board.legal_moves().into_iter().find(|m| m.promotion() != 0),
+        };
+        assert_eq!(table.probe(board.hash()), None);
+        table.store(board.hash(), entry.clone());
+        assert_eq!(table.probe(board.hash()), Some(entry.clone()));
+        // a shallower result does not replace a deeper one
+        table.store(board.hash(), Entry { depth: 2, ..entry.clone() });
+        assert_eq!(table.probe(board.hash()), Some(entry));
+        table.clear();
+        assert_eq!(table.probe(board.hash()), None);
+
+        // the table does not change the value of a mate, helpers fill the shared table
+        let mut board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let table = Arc::new(TranspositionTable::new(1));
+        let options = SearchOptions {
+            threads: 3,
+            table: Some(table.clone()),
+            ..SearchOptions::new(3)
+        };
+        let mut infos = Vec::new();
+        let lines = bot::search_observed(&mut board, &options, &mut |info: &bot::SearchInfo| {
+            infos.push(info.clone())
+        });
+        assert_eq!(lines[0].score, bot::MATE_SCORE - 1);
+        assert_eq!(board.move_to_uci(&lines[0].moves[0]), "a1a8");
+        let mut child = board.clone();
+        child.push(child.move_from_uci("g1f1").unwrap()).unwrap();
+        assert!(table.probe(child.hash()).is_some_and(|entry| entry.depth >= 2));
+        assert!(infos.windows(2).all(|pair| pair[0].nodes <= pair[1].nodes));
+
+        let mut output = Vec::new();
+        let input = "setoption name Threads value 2\nsetoption name Hash value 1\n\
+            position startpos moves e2e4 e7e5 d1h5 b8c6 f1c4 g8f6\ngo depth 3\n";
+        uci::UciEngine::new().run(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().last(), Some("bestmove h5f7"));
+    }
+
+    #[test]
+    fn ponder() {
+        let run = |input: &str| {
+            let mut output = Vec::new();
+            uci::UciEngine::new().run(input.as_bytes(), &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let output = run("position startpos\ngo depth 2\n");
+        let bestmove = output.lines().last().unwrap();
+        assert!(bestmove.starts_with("bestmove ") && bestmove.contains(" ponder "));
+
+        // the best move of a ponder search waits for the ponder hit
+        let output = run("position startpos\ngo ponder depth 1\nisready\nponderhit\n");
+        let lines: Vec<&str> = output.lines().collect();
+        let ready = lines.iter().position(|line| *line == "readyok").unwrap();
+        assert!(lines[ready + 1..].iter().any(|line| line.starts_with("bestmove ")));
+        let output = run("position startpos\ngo ponder wtime 1000 btime 1000\nstop\n");
+        assert!(output.lines().last().unwrap().starts_with("bestmove "));
+
+        // the engine thinks on its opponent's time
+        let mut game = Game::new(
+            Box::new(EnginePlayer::new(2).pondering()),
+            Box::new(EnginePlayer::new(1)),
+        );
+        while let Ok(None) = game.step() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(game.player(true).thinking().is_some());
+        assert!(matches!(game.step(), Ok(Some(_))));
+        while let Ok(None) = game.step() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(game.board().ply(), 3);
+    }
+
+    #[test]
+    fn strength() {
+        use bot::{Line, SearchOptions};
+        use rand::SeedableRng;
+
+        for strength in Strength::levels() {
+            assert_eq!(Strength::from_elo(strength.elo()), strength);
+        }
+        assert_eq!(Strength::from_elo(0), Strength::new(1));
+        assert_eq!(Strength::from_elo(3000), Strength::default());
+        assert_eq!(Strength::new(30).to_string(), "Level 20 (2400 Elo)");
+        assert_eq!(Strength::new(1).depth(), Some(1));
+        assert_eq!(Strength::new(19).depth(), Some(5));
+        assert_eq!((Strength::default().depth(), Strength::default().nodes()), (None, None));
+
+        // only lines close to the best one are chosen, full strength plays the best one
+        let board = Board::new();
+        let moves = board.legal_moves();
+        let lines: Vec<Line> = [0, -5, -500]
+            .iter()
+            .zip(&moves)
+            .map(|(score, _move)| Line {
+                moves: vec![_move.clone()],
+                score: *score,
+            })
+            .collect();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let chosen: Vec<i32> = (0..50)
+            .map(|_| Strength::new(19).choose(&lines, &mut rng).unwrap().score)
+            .collect();
+        assert!(chosen.contains(&0) && chosen.contains(&-5) && !chosen.contains(&-500));
+        assert_eq!(Strength::default().choose(&lines, &mut rng), Some(&lines[0]));
+
+        // an iteration going past the node limit is discarded
+        let options = SearchOptions {
+            nodes: Some(100),
+            ..SearchOptions::new(5)
+        };
+        let mut depths = Vec::new();
+        let mut observer = |info: &bot::SearchInfo| depths.push(info.depth);
+        let lines = bot::search_observed(&mut Board::new(), &options, &mut observer);
+        assert_eq!((depths, lines.len()), (vec![1, 2], 1));
+
+        let mut output = Vec::new();
+        let input = "setoption name UCI_LimitStrength value true\n\
+            setoption name UCI_Elo value 800\nposition startpos\ngo depth 4\n";
+        uci::UciEngine::new().run(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.lines().all(|line| !line.starts_with("info depth 2")));
+        let bestmove = output.lines().last().unwrap().strip_prefix("bestmove ").unwrap();
+        assert!(board.move_from_uci(&bestmove[..4]).is_some());
+
+        let mut game = Game::new(
+            Box::new(EnginePlayer::new(5).with_strength(Strength::new(1))),
+            Box::new(RandomPlayer::from_seed(1)),
+        );
+        assert_eq!(game.player(true).name(), "Bot (level 1)");
+        for _ in 0..10 {
+            game.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn promotion_lookup() {
+        let board = Board::from_fen("1n6/P7/8/8/8/8/8/k6K w - - 0 1");
+        let (a7, a8, b8) = (Position::new(6, 0), Position::new(7, 0), Position::new(7, 1));
+        assert!(board.is_promotion(a7, a8) && board.is_promotion(a7, b8));
+        assert!(!board.is_promotion(Position::new(0, 7), Position::new(1, 7)));
+        for (piece, uci) in [(PieceType::Queen, "a7a8q"), (PieceType::Knight, "a7b8n")] {
+            let to = if uci.ends_with('q') { a8 } else { b8 };
+            let _move = board.find_move(a7, to, Some(piece)).unwrap();
+            assert_eq!(board.move_to_uci(&_move), uci);
+        }
+        assert_eq!(board.find_move(a7, a8, None), None);
+        assert_eq!(board.find_move(a7, a8, Some(PieceType::King)), None);
+        let king = board.find_move(Position::new(0, 7), Position::new(1, 7), None).unwrap();
+        assert_eq!(board.move_to_uci(&king), "h1h2");
+    }
+
+    #[test]
+    fn last_move_and_check() {
+        let mut board = Board::default();
+        assert_eq!(board.last_move(), None);
+        for uci in ["e2e4", "f7f6", "d2d4", "g7g5"] {
+            board.push(board.move_from_uci(uci).unwrap()).unwrap();
+        }
+        let (g7, g5) = (Position::new(6, 6), Position::new(4, 6));
+        assert_eq!(board.last_move(), Some((g7, g5)));
+        assert_eq!(board.checked_king(), None);
+        board.push(board.move_from_uci("d1h5").unwrap()).unwrap();
+        // black is mated, the king stays on e8
+        assert_eq!(board.checked_king(), Some(Position::new(7, 4)));
+        board.pop();
+        assert_eq!(board.last_move(), Some((g7, g5)));
+    }
+
+    #[test]
+    fn fen_validation() {
+        let fen = "r3k2r/8/8/8/4Pp2/8/8/R3K2R b KQkq e3 0 1";
+        assert!(!Board::try_from_fen(fen).unwrap().white_turn());
+        assert!(Board::try_from_fen(PerftPositions::POSITION2).is_ok());
+        // extra whitespace between and before the fields is ignored
+        let start = Board::new().fen();
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w  KQkq - 0 1",
+            " rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        ] {
+            assert_eq!(Board::try_from_fen(fen).unwrap().fen(), start);
+        }
+        for invalid in [
+            "",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e4 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQQBNR w KQkq - 0 1",
+            "P3k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            // the king of the side not to move is attacked
+            "4k3/4R3/8/8/8/8/8/4K3 w - - 0 1",
+        ] {
+            assert!(Board::try_from_fen(invalid).is_err(), "{}", invalid);
+        }
+    }
 }
@@ -1,17 +1,28 @@
+mod board_view;
+
+use board_view::BoardView;
+use chess::bot::{self, BackgroundSearch, Line, SearchInfo, SearchOptions};
+use chess::clock::format_time;
 use chess::position::Position;
-use chess::{board::PerftPositions, Board, PlayerType};
-use iced::alignment::{Horizontal, Vertical};
-use iced::theme::{self, Button, Theme};
-use iced::widget::button::{Appearance, StyleSheet};
-use iced::widget::{button, checkbox, container, text, Column, Container, Row};
-use iced::{executor, Font};
-use iced::{window, Renderer};
-use iced::{Alignment, Color, Command, Element, Length, Sandbox, Settings, Subscription};
+use chess::{
+    BackgroundPlayer, Board, Book, Clock, EnginePlayer, Game, GameResult, GuiHandle, GuiPlayer,
+    PieceType, Player, PlayerAction, Reason, Strength, UciPlayer,
+};
+use iced::alignment::Horizontal;
+use iced::theme::{self, Theme};
+use iced::widget::{
+    button, container, pick_list, radio, scrollable, text, text_input, Column, Row,
+};
+use iced::{executor, time, window, Alignment, Color, Renderer};
+use iced::{Application, Command, Font, Subscription};
+use iced::{Element, Length, Settings};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 fn main() -> iced::Result {
     Chess::run(Settings {
         window: window::Settings {
-            size: (600, 565),
+            size: (900, 665),
             resizable: false,
             ..window::Settings::default()
         },
@@ -19,16 +30,179 @@ fn main() -> iced::Result {
     })
 }
 
+/// who plays the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    HumanVsBot,
+    BotVsBot,
+    /// two humans taking turns on the same board
+    Hotseat,
+}
+
+/// the color of the human playing against the bot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    White,
+    Black,
+    Random,
+}
+
+/// the settings of a game, edited in the new game dialog
+#[derive(Debug, Clone)]
+struct Setup {
+    mode: Mode,
+    side: Side,
+    strength: Strength,
+    /// the time control in the format of `--tc`, empty for unlimited time
+    time_control: String,
+    /// the start position, empty for the standard one
+    fen: String,
+    /// why the game could not be started with these settings
+    error: Option<String>,
+}
+
+impl Setup {
+    /// creates the game and the handle of its human players, fails on an invalid time control
+    /// or start position
+    fn start(&self, engine: &Engine) -> Result<(Game, GuiHandle), String> {
+        let clock = match self.time_control.trim() {
+            "" => Clock::unlimited(),
+            time_control => time_control.parse()?,
+        };
+        let fen = self.fen.trim();
+        if !fen.is_empty() && Board::try_from_fen(fen)?.legal_moves().is_empty() {
+            return Err(String::from("the game is already over in this position"));
+        }
+        let handle = GuiHandle::default();
+        let human = || -> Box<dyn Player> { Box::new(GuiPlayer::with_handle(handle.clone())) };
+        let human_white = match self.side {
+            Side::White => true,
+            Side::Black => false,
+            Side::Random => rand::random(),
+        };
+        let (white, black) = match self.mode {
+            Mode::HumanVsBot if human_white => (human(), engine.player(true)?),
+            Mode::HumanVsBot => (engine.player(true)?, human()),
+            Mode::BotVsBot => (engine.player(false)?, engine.player(false)?),
+            Mode::Hotseat => (human(), human()),
+        };
+        let mut game = if fen.is_empty() {
+            Game::new(white, black)
+        } else {
+            Game::from_fen(fen, white, black)
+        };
+        game.set_clock(clock);
+        if self.strength != Strength::default() {
+            for white in [true, false] {
+                game.player_mut(white).set_strength(self.strength);
+            }
+        }
+        Ok((game, handle))
+    }
+
+    /// the same settings with the colors swapped
+    fn rematch(&self) -> Setup {
+        let side = match self.side {
+            Side::White => Side::Black,
+            Side::Black => Side::White,
+            Side::Random => Side::Random,
+        };
+        Setup {
+            side,
+            ..self.clone()
+        }
+    }
+}
+
+/// the bot given on the command line
+#[derive(Clone, Default)]
+struct Engine {
+    book: Option<Book>,
+    /// path of a UCI engine playing instead of the built in one
+    uci: Option<String>,
+}
+
+impl Engine {
+    /// a new bot, thinking on the opponent's time if `ponder`
+    fn player(&self, ponder: bool) -> Result<Box<dyn Player>, String> {
+        if let Some(path) = &self.uci {
+            let engine = UciPlayer::new(path)
+                .map_err(|error| format!("unable to start engine: {}", error))?;
+            // waiting for the engine would block the interface
+            return Ok(Box::new(BackgroundPlayer::new(Box::new(engine))));
+        }
+        let mut bot = if ponder {
+            EnginePlayer::new(5).pondering()
+        } else {
+            EnginePlayer::new(5).in_background()
+        };
+        if let Some(book) = &self.book {
+            bot = bot.with_book(book.clone());
+        }
+        Ok(Box::new(bot))
+    }
+}
+
 struct Chess {
-    board: Board,
+    game: Game,
+    handle: GuiHandle,
+    engine: Engine,
+    /// the settings of the current game, used for a rematch
+    setup: Setup,
+    /// the settings edited in the new game dialog while it is open
+    new_game: Option<Setup>,
     active_piece: Option<Position>,
+    /// the move of a pawn to the last rank waiting for the piece to promote to
+    promotion: Option<(Position, Position)>,
+    offer_draw: bool,
+    /// difficulty of the engine opponent
+    strength: Strength,
+    analysis: bool,
+    /// the best lines of the position with the hash `analyzed`
+    lines: Vec<Line>,
+    analyzed: Option<u64>,
+    analysis_search: Option<BackgroundSearch>,
+    /// depth and nodes of the last iteration of the analysis
+    analysis_progress: Arc<Mutex<Option<(i32, u64)>>>,
+    /// an earlier position shown read-only and its ply, `None` shows the game
+    viewed: Option<(usize, Board)>,
+    /// whether black is shown at the bottom of the board
+    flipped: bool,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Message {
-    HighlightMessage(Position),
-    DragMessage,
-    DropMessage,
+    /// a piece was picked up
+    DragMessage(Position),
+    /// the picked up piece was dropped on a square, or outside the board
+    DropMessage(Option<Position>),
+    /// the piece chosen for a promotion, `None` takes the move back
+    PromoteMessage(Option<PieceType>),
+    Tick,
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    ToggleAnalysis,
+    AnalysisFinished,
+    SetStrength(Strength),
+    /// shows the position after the given number of plies
+    ViewMessage(usize),
+    /// takes back the last move of the human and the reply
+    TakebackMessage,
+    FlipBoard,
+    /// opens the new game dialog
+    NewGame,
+    SetupMode(Mode),
+    SetupSide(Side),
+    SetupStrength(Strength),
+    SetupTimeControl(String),
+    SetupFen(String),
+    StartGame,
+    CancelNewGame,
+    /// a new game with the same settings and the colors swapped
+    Rematch,
+    /// the engine found its move
+    EngineReady,
 }
 
 // struct DarkButtonTheme {
@@ -48,96 +222,485 @@ const MESLO_LG_FONT: Font = Font::External {
     bytes: include_bytes!("../fonts/Meslo LG L DZ Regular Nerd Font Complete Mono.ttf"),
 };
 
+const ANALYSIS_DEPTH: i32 = 4;
+const ANALYSIS_LINES: usize = 3;
+
+/// a score in centipawns from the view of white as pawns, or M and the moves for a mate
+fn format_score(score: i32) -> String {
+    if let Some(moves) = bot::mate_in(score) {
+        format!("{}M{}", if score > 0 { "+" } else { "-" }, moves.abs())
+    } else {
+        format!("{:+.2}", score as f32 / 100.0)
+    }
+}
+
 impl Chess {
-    fn create_button(&self, pos: Position) -> Container<Message, Renderer> {
-        let white_text_color: Color = Color::from([0.9, 0.9, 0.9]);
-        let black_text_color: Color = Color::from([0.1, 0.1, 0.1]);
+    fn from_setup(mut setup: Setup, engine: Engine) -> Result<Chess, String> {
+        let (game, handle) = setup.start(&engine)?;
+        let human_white = game.player(true).is_human();
+        if setup.mode == Mode::HumanVsBot {
+            // a rematch swaps the colors drawn at random
+            setup.side = if human_white {
+                Side::White
+            } else {
+                Side::Black
+            };
+        }
+        setup.error = None;
+        Ok(Chess {
+            flipped: !human_white && game.player(false).is_human(),
+            game,
+            handle,
+            engine,
+            strength: setup.strength,
+            setup,
+            new_game: None,
+            active_piece: None,
+            promotion: None,
+            offer_draw: false,
+            analysis: false,
+            lines: Vec::new(),
+            analyzed: None,
+            analysis_search: None,
+            analysis_progress: Arc::default(),
+            viewed: None,
+        })
+    }
 
-        let piece = self.board.get_piece_at_position(pos);
+    /// replaces the game with a new one, invalid settings keep the dialog open with the error
+    fn start_game(&mut self, setup: Setup) -> Command<Message> {
+        match Chess::from_setup(setup.clone(), self.engine.clone()) {
+            Ok(chess) => {
+                let analysis = self.analysis;
+                *self = Chess { analysis, ..chess };
+                self.advance()
+            }
+            Err(error) => {
+                self.new_game = Some(Setup {
+                    error: Some(error),
+                    ..setup
+                });
+                Command::none()
+            }
+        }
+    }
 
-        let mut chess_button = button(
-            text(piece.as_unicode_char_abs())
-                .horizontal_alignment(Horizontal::Center)
-                .vertical_alignment(Vertical::Center)
-                .width(Length::Fill)
-                .height(Length::Fill)
-                .size(150)
-                .font(MESLO_LG_FONT)
-                .style(if piece.is_white() {
-                    white_text_color
-                } else {
-                    black_text_color
-                }),
-        )
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .style(if (pos.file + pos.rank) % 2 == 0 {
-            Button::Primary
+    fn edit_setup(&mut self, edit: impl FnOnce(&mut Setup)) {
+        if let Some(setup) = &mut self.new_game {
+            edit(setup);
+            setup.error = None;
+        }
+    }
+
+    /// steps the game until a human is to move or a bot thinks in the background
+    fn advance(&mut self) -> Command<Message> {
+        while !self.game.is_over() && !self.game.human_turn() {
+            if !self.step() {
+                return Self::wait_for_engine(&self.game);
+            }
+        }
+        Command::none()
+    }
+
+    /// asks the player to move, returns whether a move was played. A player choosing an
+    /// illegal move loses as if it resigned
+    fn step(&mut self) -> bool {
+        match self.game.step() {
+            Ok(_move) => _move.is_some(),
+            Err(error) => {
+                let white_turn = self.game.board().white_turn();
+                eprintln!("{} chose an {}", self.game.player(white_turn).name(), error);
+                let result = GameResult::win_for(!white_turn, Reason::Resignation);
+                self.game.set_result(result);
+                false
+            }
+        }
+    }
+
+    /// starts searching the best lines of the current position if the analysis is shown
+    fn update_analysis(&mut self) -> Command<Message> {
+        let board = self.game.board();
+        if !self.analysis || self.analyzed == Some(board.hash()) {
+            return Command::none();
+        }
+        let options = SearchOptions {
+            lines: ANALYSIS_LINES,
+            ..SearchOptions::new(ANALYSIS_DEPTH)
+        };
+        let progress = self.analysis_progress.clone();
+        *progress.lock().unwrap() = None;
+        let search = BackgroundSearch::start(board, options, move |info: &SearchInfo| {
+            *progress.lock().unwrap() = Some((info.depth, info.nodes));
+        });
+        let finished = search.finished();
+        // the search of the previous position is stopped when dropped
+        self.analysis_search = Some(search);
+        self.analyzed = Some(board.hash());
+        self.lines.clear();
+        Command::perform(finished, |_| Message::AnalysisFinished)
+    }
+
+    /// plays the move of the human and lets the engine reply
+    fn play(
+        &mut self,
+        from: Position,
+        to: Position,
+        promotion: Option<PieceType>,
+    ) -> Command<Message> {
+        let _move = match self.game.board().find_move(from, to, promotion) {
+            Some(_move) => _move,
+            None => return Command::none(),
+        };
+        self.handle.submit_action(if self.offer_draw {
+            PlayerAction::OfferDraw(_move)
         } else {
-            Button::Secondary
+            PlayerAction::Move(_move)
         });
+        self.offer_draw = false;
+        self.step();
+        self.advance()
+    }
 
-        if self.active_piece.is_some() {
-            // hightlight moves the piece is able to make
-            let old_pos = self.active_piece.unwrap();
-            if self.board.piece_able_to_move_to_pos(old_pos, pos) {
-                chess_button = chess_button.on_press(Message::HighlightMessage(pos));
+    /// waits for the move of the engine if it is thinking
+    fn wait_for_engine(game: &Game) -> Command<Message> {
+        match game.player_to_move().thinking() {
+            Some(thinking) if !game.is_over() => {
+                Command::perform(thinking, |_| Message::EngineReady)
             }
+            _ => Command::none(),
+        }
+    }
+
+    fn analysis_panel(&self) -> Column<'_, Message, Renderer> {
+        let label = if self.analysis {
+            "Hide analysis"
         } else {
-            // hightlight all moveable pieces
-            if !piece.is_empty() && self.board.piece_able_to_move(pos) {
-                chess_button = chess_button.on_press(Message::HighlightMessage(pos));
+            "Analyze"
+        };
+        let levels: Vec<Strength> = Strength::levels().collect();
+        let mut panel = Column::new()
+            .spacing(10)
+            .padding(10)
+            .push(
+                button(text("New game").horizontal_alignment(Horizontal::Center))
+                    .width(Length::Fill)
+                    .on_press(Message::NewGame),
+            )
+            .push(text("Difficulty").size(18))
+            .push(pick_list(levels, Some(self.strength), Message::SetStrength).width(Length::Fill))
+            .push(
+                button(text("Flip board").horizontal_alignment(Horizontal::Center))
+                    .width(Length::Fill)
+                    .on_press(Message::FlipBoard),
+            )
+            .push(
+                button(text(label).horizontal_alignment(Horizontal::Center))
+                    .width(Length::Fill)
+                    .on_press(Message::ToggleAnalysis),
+            );
+        if self.analysis {
+            if let Some((depth, nodes)) = *self.analysis_progress.lock().unwrap() {
+                panel = panel.push(text(format!("depth {}, {} nodes", depth, nodes)).size(18));
+            }
+            let board = self.game.board();
+            let sign = if board.white_turn() { 1 } else { -1 };
+            for line in &self.lines {
+                panel = panel.push(
+                    text(format!(
+                        "{}  {}",
+                        format_score(line.score * sign),
+                        line.to_san(board)
+                    ))
+                    .size(18),
+                );
             }
         }
+        panel.width(Length::Fill)
+    }
 
-        container(chess_button)
-            //.padding(10)
-            .width(Length::Fill)
+    /// the number of moves a takeback undoes, the reply of a bot is taken back with the move
+    fn takeback_plies(&self) -> usize {
+        let last_mover = self.game.player(!self.game.board().white_turn());
+        if last_mover.is_human() {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// the ply of the position shown on the board
+    fn shown_ply(&self) -> usize {
+        match &self.viewed {
+            Some((ply, _)) => *ply,
+            None => self.game.board().record().len(),
+        }
+    }
+
+    /// the moves of the game in SAN, a click on one shows the position after it
+    fn move_list(&self) -> Column<'_, Message, Renderer> {
+        let record = self.game.board().record();
+        let mut fields = record.start_fen().split_whitespace().skip(1);
+        let black_first = fields.next() == Some("b");
+        let first_move: usize = fields.nth(3).and_then(|n| n.parse().ok()).unwrap_or(1);
+        let shown = self.shown_ply();
+        let entry = |ply: usize, san: &str| {
+            let style = if ply == shown {
+                iced::theme::Button::Primary
+            } else {
+                iced::theme::Button::Text
+            };
+            button(text(san).size(18))
+                .padding(2)
+                .width(Length::Units(80))
+                .style(style)
+                .on_press(Message::ViewMessage(ply))
+        };
+        let mut moves = Column::new();
+        let mut row = Row::new();
+        // a game starting with black to move leaves the first white move empty
+        let offset = black_first as usize;
+        for (i, record_entry) in record.entries().iter().enumerate() {
+            let white = (i + offset).is_multiple_of(2);
+            if white || i == 0 {
+                let number = first_move + (i + offset) / 2;
+                row = Row::new().spacing(5).push(
+                    text(format!("{}.", number))
+                        .size(18)
+                        .width(Length::Units(40)),
+                );
+                if !white {
+                    row = row.push(text("...").size(18).width(Length::Units(80)));
+                }
+            }
+            row = row.push(entry(i + 1, &record_entry.san));
+            if !white || i + 1 == record.len() {
+                moves = moves.push(std::mem::replace(&mut row, Row::new()));
+            }
+        }
+
+        let len = record.len();
+        let nav = |label: &str, ply: usize, enabled: bool| {
+            let nav =
+                button(text(label).horizontal_alignment(Horizontal::Center)).width(Length::Fill);
+            if enabled {
+                nav.on_press(Message::ViewMessage(ply))
+            } else {
+                nav
+            }
+        };
+        let takeback_plies = self.takeback_plies();
+        let can_take_back =
+            (self.game.human_turn() || self.game.is_over()) && len >= takeback_plies;
+        let mut takeback =
+            button(text("Take back").horizontal_alignment(Horizontal::Center)).width(Length::Fill);
+        if can_take_back {
+            takeback = takeback.on_press(Message::TakebackMessage);
+        }
+        Column::new()
+            .spacing(10)
+            .padding(10)
+            .height(Length::Fill)
+            .push(text("Moves").size(18))
+            .push(scrollable(moves).height(Length::Fill))
+            .push(
+                Row::new()
+                    .spacing(5)
+                    .push(nav("<<", 0, shown > 0))
+                    .push(nav("<", shown.saturating_sub(1), shown > 0))
+                    .push(nav(">", shown + 1, shown < len))
+                    .push(nav(">>", len, shown < len)),
+            )
+            .push(takeback)
+    }
+
+    fn board_column(&self) -> Column<'_, Message, Renderer> {
+        let board = match &self.viewed {
+            // earlier positions can only be looked at
+            Some((_, board)) => BoardView::new(board, None, false).flipped(self.flipped),
+            None => {
+                let interactive = self.game.human_turn() && !self.game.is_over();
+                BoardView::new(self.game.board(), self.active_piece, interactive)
+                    .promoting(self.promotion.map(|(_, to)| to))
+                    .flipped(self.flipped)
+            }
+        };
+        let clock = |white: bool| {
+            let name = self.game.player(white).name();
+            let line = if self.game.clock().is_unlimited() {
+                name
+            } else {
+                format!("{} {}", name, format_time(self.game.time_left(white)))
+            };
+            text(line).size(24).height(Length::Units(30))
+        };
+        let mut content = Column::new()
             .height(Length::Fill)
+            .push(clock(self.flipped))
+            .push(board)
+            .push(clock(!self.flipped));
+        if self.game.is_over() {
+            let banner = Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(
+                    text(self.game.board().state.to_string())
+                        .size(24)
+                        .width(Length::Fill),
+                )
+                .push(button(text("Rematch")).on_press(Message::Rematch))
+                .push(button(text("New game")).on_press(Message::NewGame));
+            content = content.push(container(banner).padding(5).style(theme::Container::Box));
+        } else {
+            let action = |label: &str, message: Message, enabled: bool| {
+                let action = button(text(label).horizontal_alignment(Horizontal::Center))
+                    .width(Length::Fill);
+                if enabled && self.game.human_turn() {
+                    action.on_press(message)
+                } else {
+                    action
+                }
+            };
+            let offer_label = if self.offer_draw {
+                "Draw offered"
+            } else {
+                "Offer draw"
+            };
+            content = content.push(
+                Row::new()
+                    .spacing(10)
+                    .push(action(offer_label, Message::OfferDraw, true))
+                    .push(action(
+                        "Accept draw",
+                        Message::AcceptDraw,
+                        self.game.draw_offered(),
+                    ))
+                    .push(action("Resign", Message::Resign, true)),
+            );
+        }
+        content
     }
 }
 
-impl Sandbox for Chess {
+fn new_game_dialog(setup: &Setup) -> Column<'_, Message, Renderer> {
+    let heading = |label| text(label).size(18);
+    let mut dialog = Column::new()
+        .spacing(10)
+        .padding(20)
+        .height(Length::Fill)
+        .push(text("New game").size(30))
+        .push(heading("Players"));
+    let mut modes = Row::new().spacing(20);
+    for (label, mode) in [
+        ("Human vs bot", Mode::HumanVsBot),
+        ("Bot vs bot", Mode::BotVsBot),
+        ("Two humans", Mode::Hotseat),
+    ] {
+        modes = modes.push(radio(label, mode, Some(setup.mode), Message::SetupMode));
+    }
+    dialog = dialog.push(modes);
+    if setup.mode == Mode::HumanVsBot {
+        let mut sides = Row::new().spacing(20);
+        for (label, side) in [
+            ("White", Side::White),
+            ("Black", Side::Black),
+            ("Random", Side::Random),
+        ] {
+            sides = sides.push(radio(label, side, Some(setup.side), Message::SetupSide));
+        }
+        dialog = dialog.push(heading("Play as")).push(sides);
+    }
+    if setup.mode != Mode::Hotseat {
+        let levels: Vec<Strength> = Strength::levels().collect();
+        dialog = dialog.push(heading("Difficulty")).push(pick_list(
+            levels,
+            Some(setup.strength),
+            Message::SetupStrength,
+        ));
+    }
+    dialog = dialog
+        .push(heading("Time control"))
+        .push(
+            text_input(
+                "unlimited, or seconds with an increment, ex.: 300+2",
+                &setup.time_control,
+                Message::SetupTimeControl,
+            )
+            .padding(5),
+        )
+        .push(heading("Start position"))
+        .push(
+            text_input(
+                "FEN, empty for the standard position",
+                &setup.fen,
+                Message::SetupFen,
+            )
+            .padding(5),
+        );
+    if let Some(error) = &setup.error {
+        dialog = dialog.push(text(error).style(Color::from_rgb(0.9, 0.3, 0.3)));
+    }
+    let action = |label, message| {
+        button(text(label).horizontal_alignment(Horizontal::Center))
+            .width(Length::Fill)
+            .on_press(message)
+    };
+    dialog.push(
+        Row::new()
+            .spacing(10)
+            .push(action("Start", Message::StartGame))
+            .push(action("Cancel", Message::CancelNewGame)),
+    )
+}
+
+impl Application for Chess {
+    type Executor = executor::Default;
     type Message = Message;
+    type Theme = Theme;
+    type Flags = ();
 
-    fn new() -> Self {
-        let mut board = Board::new(PlayerType::HumanPlayer, PlayerType::Bot);
-        board.generate_moves();
-        if !board.human_turn() {
-            board.play_ply();
-        }
-        board.generate_moves();
-        Self {
-            board: board,
-            active_piece: None,
+    fn new(_flags: ()) -> (Self, Command<Message>) {
+        // usage: chess [--tc <time control>] [--book <polyglot book>] [<uci engine>]
+        let mut setup = Setup {
+            mode: Mode::HumanVsBot,
+            side: Side::White,
+            strength: Strength::default(),
+            time_control: String::new(),
+            fen: String::new(),
+            error: None,
+        };
+        let mut engine = Engine::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--tc" {
+                setup.time_control = args.next().unwrap_or_default();
+            } else if arg == "--book" {
+                let book =
+                    Book::open(args.next().unwrap_or_default()).expect("unable to read book");
+                engine.book = Some(book);
+            } else {
+                engine.uci = Some(arg);
+            }
         }
+        let mut chess = Chess::from_setup(setup, engine).expect("unable to start the game");
+        let command = chess.advance();
+        (chess, command)
     }
 
     fn title(&self) -> String {
-        String::from("Events - Iced")
-    }
-
-    fn view(&self) -> Element<Message> {
-        let test: Vec<i8> = (0..8).collect();
-        let board = Column::with_children(
-            test.iter()
-                .map(|file: &i8| {
-                    Row::with_children(
-                        test.iter()
-                            .map(|rank: &i8| self.create_button(Position::new(7 - *file, *rank)))
-                            .map(Element::from)
-                            .collect(),
-                    )
-                    .height(Length::Fill)
-                    .align_items(Alignment::Center)
-                })
-                .map(Element::from)
-                .collect(),
-        )
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .align_items(Alignment::Center);
-        let content = Column::new().height(Length::Fill).push(board);
+        String::from("Chess")
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let content = match &self.new_game {
+            Some(setup) => new_game_dialog(setup),
+            None => self.board_column(),
+        };
+        let content = Row::new().push(content.width(Length::Units(600))).push(
+            Column::new()
+                .width(Length::Fill)
+                .push(self.analysis_panel())
+                .push(self.move_list()),
+        );
         container(content)
             .width(Length::Fill)
             .height(Length::Fill)
@@ -146,27 +709,129 @@ impl Sandbox for Chess {
             .into()
     }
 
-    fn update(&mut self, message: Message) {
-        match message {
-            Message::HighlightMessage(pos) => {
-                if self.active_piece.is_some() && pos != self.active_piece.unwrap() {
-                    // move piece
-                    self.board.play_human_ply(self.active_piece.unwrap(), pos);
-                    if !self.board.human_turn() {
-                        self.board.play_ply();
-                    }
-                    self.board.generate_moves();
-                    self.active_piece = None;
-                } else if self.active_piece.is_some() {
-                    // unhighlight piece
-                    self.active_piece = None;
-                } else {
-                    // highlight button
-                    self.active_piece = Some(pos);
+    fn update(&mut self, message: Message) -> Command<Message> {
+        let command = match message {
+            Message::Tick => {
+                // runs the clock and lets it detect a fallen flag
+                self.step();
+                Command::none()
+            }
+            Message::Resign => {
+                self.handle.submit_action(PlayerAction::Resign);
+                self.step();
+                Command::none()
+            }
+            Message::AcceptDraw => {
+                self.handle.submit_action(PlayerAction::AcceptDraw);
+                self.step();
+                Command::none()
+            }
+            Message::EngineReady => self.advance(),
+            Message::ToggleAnalysis => {
+                self.analysis = !self.analysis;
+                self.analyzed = None;
+                self.analysis_search = None;
+                self.lines.clear();
+                Command::none()
+            }
+            Message::AnalysisFinished => {
+                if let Some(lines) = self.analysis_search.as_ref().and_then(|s| s.try_lines()) {
+                    self.lines = lines;
+                }
+                Command::none()
+            }
+            Message::SetStrength(strength) => {
+                self.strength = strength;
+                self.setup.strength = strength;
+                for white in [true, false] {
+                    self.game.player_mut(white).set_strength(strength);
                 }
+                Command::none()
             }
-            _ => {}
+            Message::ViewMessage(ply) => {
+                let board = self.game.board();
+                self.viewed = (ply < board.record().len()).then(|| (ply, board.position_at(ply)));
+                self.active_piece = None;
+                self.promotion = None;
+                Command::none()
+            }
+            Message::TakebackMessage => {
+                self.game.takeback(self.takeback_plies());
+                self.viewed = None;
+                self.active_piece = None;
+                self.promotion = None;
+                self.offer_draw = false;
+                self.advance()
+            }
+            Message::NewGame => {
+                self.new_game = Some(self.setup.clone());
+                Command::none()
+            }
+            Message::SetupMode(mode) => {
+                self.edit_setup(|setup| setup.mode = mode);
+                Command::none()
+            }
+            Message::SetupSide(side) => {
+                self.edit_setup(|setup| setup.side = side);
+                Command::none()
+            }
+            Message::SetupStrength(strength) => {
+                self.edit_setup(|setup| setup.strength = strength);
+                Command::none()
+            }
+            Message::SetupTimeControl(time_control) => {
+                self.edit_setup(|setup| setup.time_control = time_control);
+                Command::none()
+            }
+            Message::SetupFen(fen) => {
+                self.edit_setup(|setup| setup.fen = fen);
+                Command::none()
+            }
+            Message::StartGame => match self.new_game.take() {
+                Some(setup) => self.start_game(setup),
+                None => Command::none(),
+            },
+            Message::CancelNewGame => {
+                self.new_game = None;
+                Command::none()
+            }
+            Message::Rematch => self.start_game(self.setup.rematch()),
+            Message::FlipBoard => {
+                self.flipped = !self.flipped;
+                Command::none()
+            }
+            Message::OfferDraw => {
+                // the offer is made together with the next move
+                self.offer_draw = !self.offer_draw;
+                Command::none()
+            }
+            Message::DragMessage(pos) => {
+                self.active_piece = Some(pos);
+                Command::none()
+            }
+            Message::DropMessage(target) => match (self.active_piece.take(), target) {
+                (Some(from), Some(to)) if self.game.board().is_promotion(from, to) => {
+                    self.promotion = Some((from, to));
+                    Command::none()
+                }
+                (Some(from), Some(to)) => self.play(from, to, None),
+                // a drop on an illegal square cancels the move
+                _ => Command::none(),
+            },
+            Message::PromoteMessage(piece) => match (self.promotion.take(), piece) {
+                (Some((from, to)), Some(piece)) => self.play(from, to, Some(piece)),
+                _ => Command::none(),
+            },
         };
+        Command::batch([command, self.update_analysis()])
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        if self.game.clock().is_unlimited() || self.game.is_over() {
+            Subscription::none()
+        } else {
+            time::every(Duration::from_millis(100)).map(|_| Message::Tick)
+        }
     }
 
     fn theme(&self) -> Theme {
@@ -1,7 +1,8 @@
 use crate::{
-    Board, Move, Piece, _move::MoveFlags, board::Data, piece_moves::Direction, position::Position,
+    Board, Move, Piece, _move::MoveFlags, board::Data, position::Position,
 };
 
+#[derive(Clone)]
 pub(crate) struct MoveGenerator {
     pub(crate) moves: Vec<Move>,
     pub(crate) fields_under_attack: u64,
@@ -76,6 +77,59 @@ impl MoveGenerator {
         }).cloned()
     }
 
+    /// bitmask of all pieces of `color` attacking the square at `index`
+    pub(crate) fn attackers_of(board: &Data, index: u8, color: i8) -> u64 {
+        let mut attackers = 0;
+
+        for i in 0..8 {
+            // knight
+            let _move = board.piece_moves.knight_moves[i];
+            if _move.move_in_bounds(index, 1) {
+                let dest = _move + index;
+                if board.board[dest as usize].piece == 3 * color {
+                    attackers |= 1 << dest;
+                }
+            }
+
+            // king and sliding pieces
+            let _move = board.piece_moves.sliding[i];
+            for n in 1..8 {
+                if !_move.move_in_bounds(index, n) {
+                    break;
+                }
+
+                let dest = _move * n + index;
+                let piece = &board.board[dest as usize];
+
+                if piece.is_empty() {
+                    continue;
+                }
+                if piece.is_color(color)
+                    && (piece.is_queen()
+                        || (piece.is_king() && n == 1)
+                        || (piece.is_rook() && i >= 4)
+                        || (piece.is_bishop() && i < 4))
+                {
+                    attackers |= 1 << dest;
+                }
+                break;
+            }
+        }
+
+        // pawn
+        for i in 1..3 {
+            let _move = board.piece_moves.pawn_moves[i];
+            if _move.move_in_bounds(index, -color) {
+                let dest = _move * -color + index;
+                if board.board[dest as usize].piece == color {
+                    attackers |= 1 << dest;
+                }
+            }
+        }
+
+        attackers
+    }
+
     fn calculate_attack_data(&mut self, board: &Data) {
         // king
         for i in 0..8 {
@@ -104,7 +158,7 @@ impl MoveGenerator {
             }
         }
         // pawn
-        for (_, index) in board.piece_list.from_type(self.color * 1) {
+        for (_, index) in board.piece_list.from_type(self.color) {
             for i in 1..3 {
                 let _move = board.piece_moves.pawn_moves[i];
                 let dest = _move * self.color + *index;
@@ -176,11 +230,13 @@ impl MoveGenerator {
                     // indirect attack on king via pinning
                     let uuid = pinning_piece.uuid & 0x0f;
                     self.pinned |= 1 << uuid;
-                    self.pinned_dir |= ((direction_index / 2) as u32) << 2 * uuid;
+                    self.pinned_dir |= ((direction_index / 2) as u32) << (2 * uuid);
                     break;
                 } else if pinning_piece.is_empty() && enemy_piece && attacking_king && is_capture {
-                    // pinning piece
+                    // pinning piece, its square is still under attack, which matters when the
+                    // king castles onto the square of its rook in Chess960
                     pinning_piece = target;
+                    self.fields_under_attack |= 1 << dest;
                 } else if !pinning_piece.is_empty() && is_capture {
                     // second enemy piece in dir
                     break;
@@ -207,36 +263,67 @@ impl MoveGenerator {
                 && !self.fields_under_attack & (1 << dest) != 0
             {
                 self.moves.push(Move::from_flags(index, dest, 0));
+            }
+        }
 
-                if !self.in_check && board.board[dest as usize].is_empty() {
-                    // Castle kingside
-                    if i == Direction::kingside(self.color)
-                        && board.not_able_to_castle & (0x02 << (2 * (!board.white_turn as u8))) == 0
-                        && board.board[dest as usize + 1].is_empty()
-                        && !self.fields_under_attack & (1 << (dest + 1)) != 0
-                    {
-                        self.moves.push(Move::from_flags(
-                            self.king_index,
-                            dest + 1,
-                            MoveFlags::KINGSIDE_CASTLING,
-                        ));
-                    }
-                    // Castle queenside
-                    else if i == Direction::queenside(self.color)
-                        && board.not_able_to_castle & (0x01 << (2 * (!board.white_turn as u8))) == 0
-                        && board.board[dest as usize - 1].is_empty()
-                        && board.board[dest as usize - 2].is_empty()
-                        && !self.fields_under_attack & (1 << (dest - 1)) != 0
-                    {
-                        self.moves.push(Move::from_flags(
-                            self.king_index,
-                            dest - 1,
-                            MoveFlags::QUEENSIDE_CASTLING,
-                        ));
+        if !self.in_check {
+            self.generate_castling_moves(board, MoveFlags::QUEENSIDE_CASTLING);
+            self.generate_castling_moves(board, MoveFlags::KINGSIDE_CASTLING);
+        }
+    }
+
+    fn generate_castling_moves(&mut self, board: &Data, castling: u8) {
+        // works for standard chess as well as Chess960, where king and rook may start anywhere
+        // on the back rank but always end up on the same squares as in standard chess
+        let kingside = castling == MoveFlags::KINGSIDE_CASTLING;
+        let bit = kingside as u8 + 2 * (!board.white_turn as u8);
+
+        if board.not_able_to_castle & (1 << bit) != 0 {
+            return;
+        }
+
+        let back_rank = (self.king_index / 8) * 8;
+        let king_start = self.king_index;
+        let rook_start = board.castling_rooks[bit as usize];
+        let king_end = back_rank + if kingside { 6 } else { 2 };
+        let rook_end = back_rank + if kingside { 5 } else { 3 };
+
+        debug_assert!(board.board[rook_start as usize].is_rook());
+
+        // all squares the king and rook pass need to be empty, except for king and rook
+        let lowest = king_start.min(king_end).min(rook_start).min(rook_end);
+        let highest = king_start.max(king_end).max(rook_start).max(rook_end);
+        for dest in lowest..=highest {
+            if dest != king_start && dest != rook_start && !board.board[dest as usize].is_empty() {
+                return;
+            }
+        }
+
+        // the king must not pass an attacked square
+        for dest in king_start.min(king_end)..=king_start.max(king_end) {
+            if self.fields_under_attack & (1 << dest) != 0 {
+                return;
+            }
+        }
+
+        // the castling rook might be shielding the end square of the king from a rook or
+        // queen further along the back rank
+        if (rook_start > king_end) == kingside {
+            let direction: i8 = if kingside { 1 } else { -1 };
+            let mut dest = king_end as i8 + direction;
+            while dest >= back_rank as i8 && dest < back_rank as i8 + 8 {
+                let piece = &board.board[dest as usize];
+                if dest as u8 != rook_start && dest as u8 != king_start && !piece.is_empty() {
+                    if piece.is_color(-self.color) && (piece.is_rook() || piece.is_queen()) {
+                        return;
                     }
+                    break;
                 }
+                dest += direction;
             }
         }
+
+        self.moves.push(Move::from_flags(king_start, king_end, castling));
     }
 
     fn generate_knight_moves(&mut self, board: &Data) {
@@ -320,7 +407,7 @@ impl MoveGenerator {
         let start_file = 1 + (5 * !board.white_turn as u8);
         let penultimate_file = 1 + (5 * board.white_turn as u8);
 
-        for (_, index) in board.piece_list.from_type(self.color * 1) {
+        for (_, index) in board.piece_list.from_type(self.color) {
             let uuid = board.board[*index as usize].uuid & 0x0f;
             let dest_one_forward = (*index as i8 + 16 * self.color) as u8;
             for i in 0..3 {
@@ -336,7 +423,7 @@ impl MoveGenerator {
                 if self.pinned & (1 << uuid) != 0
                     && !self.moving_along_pinned_dir(
                         uuid,
-                        board.piece_moves.pawn_direction_index[i as usize],
+                        board.piece_moves.pawn_direction_index[i],
                     )
                 {
                     // if not moving along pinned direction
@@ -465,11 +552,7 @@ impl MoveGenerator {
             let dest = (self.king_index as i8 + n as i8 * direction) as u8;
             let piece = &board.board[dest as usize];
             if dest != start && dest != captured && !piece.is_empty() {
-                if piece.is_color(-self.color) && (piece.is_rook() || piece.is_queen()) {
-                    return true;
-                } else {
-                    return false;
-                }
+                return piece.is_color(-self.color) && (piece.is_rook() || piece.is_queen());
             }
         }
         false
@@ -0,0 +1,121 @@
+use crate::Board;
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// a game read from PGN, the moves are kept in SAN until the game is replayed
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// plays the first `max_ply` moves from the starting position of the game
+    pub fn board(&self, max_ply: usize) -> Result<Board, String> {
+        let mut board = match self.tag("FEN") {
+            Some(fen) => {
+                Board::try_from_fen(fen).map_err(|err| format!("invalid FEN {}: {}", fen, err))?
+            }
+            None => Board::new(),
+        };
+        for san in self.moves.iter().take(max_ply) {
+            let _move = board
+                .move_from_san(san)
+                .ok_or_else(|| format!("illegal move {}", san))?;
+            board.push(_move).map_err(|err| err.to_string())?;
+        }
+        Ok(board)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.moves.is_empty()
+    }
+}
+
+/// reads all games of a PGN file, comments, variations and annotations are skipped
+pub fn parse_games(text: &str) -> Vec<PgnGame> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    let mut chars = text.chars().peekable();
+    let mut variation_depth: usize = 0;
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        let at_line_start = line_start;
+        line_start = c == '\n';
+        match c {
+            '{' => {
+                chars.by_ref().find(|c| *c == '}');
+            }
+            ';' => {
+                chars.by_ref().find(|c| *c == '\n');
+                line_start = true;
+            }
+            '%' if at_line_start => {
+                chars.by_ref().find(|c| *c == '\n');
+                line_start = true;
+            }
+            '(' => variation_depth += 1,
+            ')' => variation_depth = variation_depth.saturating_sub(1),
+            '[' if variation_depth == 0 => {
+                // tags after the movetext belong to the next game
+                if !game.moves.is_empty() {
+                    games.push(std::mem::take(&mut game));
+                }
+                let tag: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                if let Some(tag) = parse_tag(&tag) {
+                    game.tags.push(tag);
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut token = String::from(c);
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{;()[".contains(*c)) {
+                    token.push(c);
+                }
+                if variation_depth > 0 || token.starts_with('$') {
+                    continue;
+                }
+                if RESULTS.contains(&token.as_str()) {
+                    game.result = token;
+                    games.push(std::mem::take(&mut game));
+                    continue;
+                }
+
+                // move numbers may be attached to the move, ex.: 12.Nf3
+                let san = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                if !san.is_empty() {
+                    game.moves.push(san.to_string());
+                }
+            }
+        }
+    }
+
+    if !game.is_empty() {
+        games.push(game);
+    }
+    for game in &mut games {
+        if game.result.is_empty() {
+            game.result = game.tag("Result").unwrap_or("*").to_string();
+        }
+    }
+    games
+}
+
+/// parses the inside of a tag pair, ex.: Event "Casual game"
+fn parse_tag(tag: &str) -> Option<(String, String)> {
+    let (name, value) = tag.trim().split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((
+        name.to_string(),
+        value.replace("\\\"", "\"").replace("\\\\", "\\"),
+    ))
+}
@@ -22,7 +22,7 @@ fn to_local(_move: u8, index: u8) -> u8 {
 }
 
 #[repr(i8)]
-#[derive(Eq, PartialEq)]
+#[derive(Eq, PartialEq, Clone, Debug, Copy)]
 pub enum PieceType {
     None = 0,
     Pawn = 1,
@@ -157,7 +157,7 @@ impl Piece {
 
     #[inline(always)]
     pub fn to_piecelist_index(&self) -> usize {
-        self.piece.abs() as usize - 1 + 3 * (self.piece.signum() + 1) as usize
+        self.piece.unsigned_abs() as usize - 1 + 3 * (self.piece.signum() + 1) as usize
     }
 
     pub fn as_char(&self) -> char {
@@ -63,12 +63,14 @@ impl PieceList {
         self.map[piece.uuid as usize] = self.pieces[index].len() as u8 - 1;
     }
 
+    #[allow(clippy::wrong_self_convention)]
     pub fn from_piece(&self, piece: &Piece) -> &Vec<(u8, u8)> {
         &self.pieces[piece.to_piecelist_index()]
     }
 
+    #[allow(clippy::wrong_self_convention)]
     pub fn from_type(&self, piece: i8) -> &Vec<(u8, u8)> {
-        &self.pieces[piece.abs() as usize - 1 + 3 * (piece.signum() + 1) as usize]
+        &self.pieces[piece.unsigned_abs() as usize - 1 + 3 * (piece.signum() + 1) as usize]
     }
 
     pub fn _move(&mut self, piece: &Piece, target: u8) {
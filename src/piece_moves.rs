@@ -14,6 +14,7 @@ pub(crate) mod Direction {
 }
 
 /// holds all possible moves for all piece types
+#[derive(Clone)]
 pub(crate) struct PieceMoves {
     pub(crate) pawn_moves: [Position; 3],
     pub(crate) knight_moves: [Position; 8],
@@ -1,89 +1,529 @@
 use std::io::Write;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
 
-use crate::bot;
-use crate::{position::Position, Board, Move};
+use crate::bot::{self, BackgroundSearch, Line, SearchFinished, SearchOptions};
+use crate::clock::Clock;
+use crate::syzygy::Syzygy;
+use crate::tablebase::Tablebases;
+use crate::{Board, Book, Move, Strength};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::SeedableRng;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub enum PlayerType {
-    HumanPlayer,
-    Bot,
-}
-
-impl PlayerType {
-    pub fn _move(&self, board: &mut Board) -> Move {
-        match self {
-            Self::HumanPlayer => loop {
-                print!("> ");
-                std::io::stdout().flush().unwrap();
-                let mut input = String::with_capacity(4);
-                std::io::stdin()
-                    .read_line(&mut input)
-                    .expect("error unable to read input");
-
-                let chars: Vec<char> = input.chars().collect();
-
-                if (chars.len() != 5 && chars.len() != 6)
-                    || !chars[0].is_ascii_alphabetic()
-                    || !chars[2].is_ascii_alphabetic()
-                    || !chars[1].is_ascii_digit()
-                    || !chars[3].is_ascii_digit()
-                {
-                    println!(
-                        "Invalid syntax, use <start><end>[<promotion piece>], ex.: a2a3, or a7a8q"
-                    );
-                    continue;
-                }
+/// what a player wants to do when it is their turn
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlayerAction {
+    Move(Move),
+    /// make the move and offer a draw to the opponent
+    OfferDraw(Move),
+    /// accept the draw offered by the opponent with their last move
+    AcceptDraw,
+    Resign,
+    /// the player has not decided yet, ask again later
+    Wait,
+}
 
-                if chars.len() == 6
-                    && !['q', 'r', 'b', 'n']
-                        .iter()
-                        .any(|c| *c == chars[4].to_ascii_lowercase())
-                {
-                    println!("Invalid <promition piece>, only 'q', 'r', 'b' and 'n' are allowed.");
-                    continue;
-                }
+pub trait Player {
+    fn choose_move(&mut self, board: &Board, clock: &Clock) -> PlayerAction;
 
-                let start = Position::new(
-                    chars[1].to_digit(10).unwrap() as i8 - 1,
-                    (chars[0].to_ascii_lowercase() as u32 - 'a' as u32) as i8,
-                );
+    /// whether the moves of this player come from a person
+    fn is_human(&self) -> bool {
+        false
+    }
+
+    fn name(&self) -> String;
+
+    /// evaluation in centipawns of the last chosen move from the view of the player, if known
+    fn score(&self) -> Option<i32> {
+        None
+    }
+
+    /// called when the opponent offers a draw, returns whether the player accepts right away
+    fn draw_offered(&mut self, _board: &Board) -> bool {
+        false
+    }
+
+    /// changes how well the player plays, players without such a setting ignore it
+    fn set_strength(&mut self, _strength: Strength) {}
+
+    /// resolves once a player who answered `PlayerAction::Wait` is ready to move, `None` if
+    /// the player waits for input from elsewhere
+    fn thinking(&self) -> Option<SearchFinished> {
+        None
+    }
+}
+
+/// whether an engine accepts a draw offer given the score of its last search, it does if it
+/// saw itself behind
+pub(crate) fn accepts_draw(score: Option<i32>) -> bool {
+    score.is_some_and(|score| score < 0)
+}
+
+/// human player entering moves in UCI notation on stdin, "resign", "accept" to accept a draw
+/// offer or "<move> draw" to offer a draw
+pub struct TerminalPlayer;
 
-                let end = Position::new(
-                    chars[3].to_digit(10).unwrap() as i8 - 1,
-                    (chars[2].to_ascii_lowercase() as u32 - 'a' as u32) as i8,
+impl Player for TerminalPlayer {
+    fn choose_move(&mut self, board: &Board, _clock: &Clock) -> PlayerAction {
+        loop {
+            print!("> ");
+            std::io::stdout().flush().unwrap();
+            let mut input = String::with_capacity(6);
+            std::io::stdin()
+                .read_line(&mut input)
+                .expect("error unable to read input");
+
+            let input = input.trim().to_ascii_lowercase();
+            match input.as_str() {
+                "resign" => return PlayerAction::Resign,
+                "accept" => return PlayerAction::AcceptDraw,
+                _ => {}
+            }
+            // a draw is offered with "<move> draw"
+            let (input, offer_draw) = match input.strip_suffix("draw") {
+                Some(input) => (input.trim().to_string(), true),
+                None => (input, false),
+            };
+
+            let chars: Vec<char> = input.chars().collect();
+
+            if (chars.len() != 4 && chars.len() != 5)
+                || !chars[0].is_ascii_alphabetic()
+                || !chars[2].is_ascii_alphabetic()
+                || !chars[1].is_ascii_digit()
+                || !chars[3].is_ascii_digit()
+            {
+                println!(
+                    "Invalid syntax, use <start><end>[<promotion piece>], ex.: a2a3, or a7a8q"
                 );
+                continue;
+            }
 
-                let promotion: u8 = if chars.len() == 6 {
-                    ['q', 'r', 'b', 'n']
-                        .iter()
-                        .position(|c| *c == chars[4].to_ascii_lowercase())
-                        .unwrap() as u8
-                        + 1
-                } else {
-                    0
-                };
-
-                if !start.move_in_bounds(0, 0) {
-                    println!("Invalid start position");
-                    continue;
-                }
+            if chars.len() == 5
+                && !['q', 'r', 'b', 'n']
+                    .iter()
+                    .any(|c| *c == chars[4].to_ascii_lowercase())
+            {
+                println!("Invalid <promition piece>, only 'q', 'r', 'b' and 'n' are allowed.");
+                continue;
+            }
 
-                if !end.move_in_bounds(0, 0) {
-                    println!("Invalid end position");
-                    continue;
-                }
+            match board.move_from_uci(&input) {
+                Some(_move) if offer_draw => return PlayerAction::OfferDraw(_move),
+                Some(_move) => return PlayerAction::Move(_move),
+                None => println!("Invalid move"),
+            }
+        }
+    }
 
-                let _move = Move::from_positions(start, end, 0, promotion, false, false);
-                let _move = board.move_generator.get_move(start, end, promotion);
-                if _move.is_none() {
-                    println!("Invalid move");
-                    continue;
-                }
+    fn is_human(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> String {
+        String::from("Human")
+    }
+}
+
+/// used by the gui to pass the actions of a `GuiPlayer`
+#[derive(Clone, Default)]
+pub struct GuiHandle(Arc<Mutex<Option<PlayerAction>>>);
+
+impl GuiHandle {
+    pub fn submit(&self, _move: Move) {
+        self.submit_action(PlayerAction::Move(_move));
+    }
+
+    pub fn submit_action(&self, action: PlayerAction) {
+        *self.0.lock().unwrap() = Some(action);
+    }
+}
+
+/// human player moving pieces in the gui, waits until an action is submitted through its handle
+pub struct GuiPlayer {
+    handle: GuiHandle,
+}
 
-                return _move.unwrap();
+impl GuiPlayer {
+    pub fn new() -> (GuiPlayer, GuiHandle) {
+        let handle = GuiHandle::default();
+        (
+            GuiPlayer {
+                handle: handle.clone(),
             },
-            Self::Bot => bot::make_move(board),
+            handle,
+        )
+    }
+
+    /// a player making the moves submitted through `handle`, players taking turns can share one
+    pub fn with_handle(handle: GuiHandle) -> GuiPlayer {
+        GuiPlayer { handle }
+    }
+}
+
+impl Player for GuiPlayer {
+    fn choose_move(&mut self, _board: &Board, _clock: &Clock) -> PlayerAction {
+        self.handle
+            .0
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or(PlayerAction::Wait)
+    }
+
+    fn is_human(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> String {
+        String::from("Human")
+    }
+}
+
+/// the built in engine searching to a fixed depth, or less if the clock is running low or its
+/// strength is limited
+pub struct EnginePlayer {
+    depth: i32,
+    strength: Option<Strength>,
+    score: Option<i32>,
+    book: Option<Book>,
+    tablebases: Option<Arc<Tablebases>>,
+    syzygy: Option<Arc<Syzygy>>,
+    background: bool,
+    ponder: bool,
+    /// the search running in the background, the hash of the position searched and whether it
+    /// was started as a ponder search still waiting for its ponder hit
+    search: Option<(u64, BackgroundSearch, bool)>,
+}
+
+impl EnginePlayer {
+    pub fn new(depth: i32) -> EnginePlayer {
+        EnginePlayer {
+            depth,
+            strength: None,
+            score: None,
+            book: None,
+            tablebases: None,
+            syzygy: None,
+            background: false,
+            ponder: false,
+            search: None,
         }
     }
+
+    /// plays moves from `book` while the position is in it
+    pub fn with_book(mut self, book: Book) -> EnginePlayer {
+        self.book = Some(book);
+        self
+    }
+
+    /// looks up positions with few pieces in `tablebases` instead of searching them
+    pub fn with_tablebases(mut self, tablebases: Arc<Tablebases>) -> EnginePlayer {
+        self.tablebases = Some(tablebases);
+        self
+    }
+
+    /// plays the moves keeping the value according to the Syzygy tables
+    pub fn with_syzygy(mut self, syzygy: Arc<Syzygy>) -> EnginePlayer {
+        self.syzygy = Some(syzygy);
+        self
+    }
+
+    /// searches on another thread, answering `PlayerAction::Wait` until the move is found
+    pub fn in_background(mut self) -> EnginePlayer {
+        self.background = true;
+        self
+    }
+
+    /// searches in the background and keeps thinking on the opponent's time about the position
+    /// after the expected reply
+    pub fn pondering(mut self) -> EnginePlayer {
+        self.background = true;
+        self.ponder = true;
+        self
+    }
+
+    /// plays at `strength` instead of the full strength up to the depth
+    pub fn with_strength(mut self, strength: Strength) -> EnginePlayer {
+        self.strength = Some(strength);
+        self
+    }
+
+    fn search_options(&self, board: &Board, clock: &Clock) -> SearchOptions {
+        let mut options = SearchOptions {
+            budget: clock.move_budget(board.data.white_turn),
+            tablebases: self.tablebases.clone(),
+            syzygy: self.syzygy.clone(),
+            ..SearchOptions::new(self.depth)
+        };
+        if let Some(strength) = self.strength {
+            if let Some(depth) = strength.depth() {
+                options.max_depth = self.depth.min(depth);
+            }
+            options.lines = strength.lines();
+            options.nodes = strength.nodes();
+        }
+        options
+    }
+
+    /// the line to play out of the ones found, the best one unless the strength is limited
+    fn choose_line<'a>(&self, lines: &'a [Line]) -> Option<&'a Line> {
+        match self.strength {
+            Some(strength) => strength.choose(lines, &mut rand::thread_rng()),
+            None => lines.first(),
+        }
+    }
+
+    fn start_search(&self, board: &Board, clock: &Clock, ponder: bool) -> BackgroundSearch {
+        let options = SearchOptions {
+            pondering: ponder.then(|| Arc::new(AtomicBool::new(true))),
+            ..self.search_options(board, clock)
+        };
+        BackgroundSearch::start(board, options, |_: &_| {})
+    }
+
+    fn search_in_background(&mut self, board: &Board, clock: &Clock) -> PlayerAction {
+        match self.search.take() {
+            Some((hash, search, pondering)) if hash == board.hash() => match search.try_lines() {
+                Some(lines) => {
+                    let line = self.choose_line(&lines).cloned();
+                    self.score = line.as_ref().map(|line| line.score);
+                    let _move = line
+                        .as_ref()
+                        .map(|line| line.moves[0].clone())
+                        .or_else(|| board.legal_moves().into_iter().next())
+                        .expect("no legal move left");
+                    if let Some(reply) = line.and_then(|line| line.moves.get(1).cloned()) {
+                        if self.ponder {
+                            let mut board = board.clone();
+                            board.make_move(&_move);
+                            board.make_move(&reply);
+                            // the budget of our next move, the clock is about to change
+                            let search = self.start_search(&board, clock, true);
+                            self.search = Some((board.hash(), search, true));
+                        }
+                    }
+                    PlayerAction::Move(_move)
+                }
+                None => {
+                    if pondering {
+                        // a ponder hit, the time spent pondering counts towards the budget and
+                        // the search stops at its deadline from now on
+                        search.ponderhit();
+                        let budget = clock.move_budget(board.data.white_turn);
+                        if budget.is_some_and(|budget| search.elapsed() >= budget) {
+                            search.stop();
+                        }
+                    }
+                    self.search = Some((hash, search, false));
+                    PlayerAction::Wait
+                }
+            },
+            // a search of another position, also after a ponder miss, is stopped when dropped
+            _ => {
+                let search = self.start_search(board, clock, false);
+                self.search = Some((board.hash(), search, false));
+                PlayerAction::Wait
+            }
+        }
+    }
+}
+
+impl Player for EnginePlayer {
+    fn choose_move(&mut self, board: &Board, clock: &Clock) -> PlayerAction {
+        if let Some(_move) = self
+            .book
+            .as_ref()
+            .and_then(|book| book.choose_move(board, &mut rand::thread_rng()))
+        {
+            self.score = None;
+            return PlayerAction::Move(_move);
+        }
+        if self.background {
+            return self.search_in_background(board, clock);
+        }
+
+        let mut board = board.clone();
+        if self.strength.is_some() {
+            let options = self.search_options(&board, clock);
+            let lines = bot::search_observed(&mut board, &options, &mut |_: &_| {});
+            let line = self.choose_line(&lines);
+            self.score = line.map(|line| line.score);
+            let _move = line
+                .map(|line| line.moves[0].clone())
+                .or_else(|| board.legal_moves().into_iter().next())
+                .expect("no legal move left");
+            return PlayerAction::Move(_move);
+        }
+        let (tablebases, syzygy) = (self.tablebases.as_deref(), self.syzygy.as_deref());
+        let (_move, score) = match clock.move_budget(board.data.white_turn) {
+            Some(budget) => bot::search_timed(&mut board, self.depth, budget, tablebases, syzygy),
+            None => bot::search_with_score(&mut board, self.depth, tablebases, syzygy),
+        };
+        let _move = _move
+            .or_else(|| board.legal_moves().into_iter().next())
+            .expect("no legal move left");
+        self.score = Some(score);
+        PlayerAction::Move(_move)
+    }
+
+    fn name(&self) -> String {
+        match self.strength {
+            Some(strength) => format!("Bot (level {})", strength.level()),
+            None => format!("Bot (depth {})", self.depth),
+        }
+    }
+
+    fn score(&self) -> Option<i32> {
+        self.score
+    }
+
+    fn draw_offered(&mut self, _board: &Board) -> bool {
+        accepts_draw(self.score)
+    }
+
+    fn set_strength(&mut self, strength: Strength) {
+        self.strength = Some(strength);
+    }
+
+    fn thinking(&self) -> Option<SearchFinished> {
+        self.search.as_ref().map(|(_, search, _)| search.finished())
+    }
+}
+
+/// the player thinking on the worker thread of a `BackgroundPlayer`, handing itself back with
+/// its answer
+type Thinking = JoinHandle<(Box<dyn Player + Send>, PlayerAction)>;
+
+/// lets another player choose its moves on a worker thread, answering `PlayerAction::Wait`
+/// until the move is chosen, ex.: a `UciPlayer` waiting for its engine
+pub struct BackgroundPlayer {
+    name: String,
+    /// the player while it is not thinking
+    player: Option<Box<dyn Player + Send>>,
+    /// the hash of the position the player thinks about, resolved once it chose
+    thinking: Option<(u64, Thinking, SearchFinished)>,
+    score: Option<i32>,
+    /// a strength set while thinking, passed on once the player is back
+    strength: Option<Strength>,
+}
+
+impl BackgroundPlayer {
+    pub fn new(player: Box<dyn Player + Send>) -> BackgroundPlayer {
+        BackgroundPlayer {
+            name: player.name(),
+            player: Some(player),
+            thinking: None,
+            score: None,
+            strength: None,
+        }
+    }
+}
+
+impl Player for BackgroundPlayer {
+    /// resigns if the player panics while thinking
+    fn choose_move(&mut self, board: &Board, clock: &Clock) -> PlayerAction {
+        if let Some((hash, thinking, finished)) = self.thinking.take() {
+            // the answer to another position, ex.: after a takeback, is only waited for
+            if !thinking.is_finished() {
+                self.thinking = Some((hash, thinking, finished));
+                return PlayerAction::Wait;
+            }
+            let (mut player, action) = match thinking.join() {
+                Ok(answer) => answer,
+                Err(_) => return PlayerAction::Resign,
+            };
+            if let Some(strength) = self.strength.take() {
+                player.set_strength(strength);
+            }
+            self.score = player.score();
+            self.player = Some(player);
+            if hash == board.hash() {
+                return action;
+            }
+        }
+        let mut player = match self.player.take() {
+            Some(player) => player,
+            None => return PlayerAction::Resign,
+        };
+        let hash = board.hash();
+        let (board, clock) = (board.clone(), clock.clone());
+        let finished = SearchFinished::pending();
+        let done = finished.clone();
+        let thinking = thread::spawn(move || {
+            let action = player.choose_move(&board, &clock);
+            done.finish();
+            (player, action)
+        });
+        self.thinking = Some((hash, thinking, finished));
+        PlayerAction::Wait
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn score(&self) -> Option<i32> {
+        self.score
+    }
+
+    /// declines while the player is thinking
+    fn draw_offered(&mut self, board: &Board) -> bool {
+        self.player
+            .as_mut()
+            .is_some_and(|player| player.draw_offered(board))
+    }
+
+    fn set_strength(&mut self, strength: Strength) {
+        match &mut self.player {
+            Some(player) => player.set_strength(strength),
+            None => self.strength = Some(strength),
+        }
+    }
+
+    fn thinking(&self) -> Option<SearchFinished> {
+        self.thinking
+            .as_ref()
+            .map(|(_, _, finished)| finished.clone())
+    }
+}
+
+/// plays a random legal move
+pub struct RandomPlayer {
+    rng: StdRng,
+}
+
+impl RandomPlayer {
+    pub fn new() -> RandomPlayer {
+        RandomPlayer {
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    pub fn from_seed(seed: u64) -> RandomPlayer {
+        RandomPlayer {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Default for RandomPlayer {
+    fn default() -> Self {
+        RandomPlayer::new()
+    }
+}
+
+impl Player for RandomPlayer {
+    fn choose_move(&mut self, board: &Board, _clock: &Clock) -> PlayerAction {
+        match board.legal_moves().choose(&mut self.rng) {
+            Some(_move) => PlayerAction::Move(_move.clone()),
+            None => PlayerAction::Wait,
+        }
+    }
+
+    fn name(&self) -> String {
+        String::from("Random")
+    }
 }
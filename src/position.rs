@@ -56,8 +56,8 @@ impl Position {
 
     pub fn is_on_path_between(&self, p1: Position, p2: Position) -> bool {
         let diff = p1 - p2;
-        let diff1 = p1 - self.clone();
-        let diff2 = self.clone() - p2;
+        let diff1 = p1 - *self;
+        let diff2 = *self - p2;
         if diff.file.abs() != diff.rank.abs() && diff.file != 0 && diff.rank != 0 {
             // invalid diff, probably from knight
             false
@@ -86,19 +86,18 @@ impl Position {
             && self.rank.signum() == direction.rank.signum()
     }
 
-    pub fn to_string(&self) -> String {
-        let mut string = String::new();
-        string.push((self.rank as u8 + 'a' as u8) as char);
-        string.push_str(&(self.file + 1).to_string());
-        string
-    }
-
     pub fn to_index(&self) -> u8 {
         debug_assert!(self.in_bounds());
         (self.file * 8 + self.rank) as u8
     }
 }
 
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", (self.rank as u8 + b'a') as char, self.file + 1)
+    }
+}
+
 impl std::ops::Add<u8> for Position {
     type Output = u8;
 
@@ -1,27 +1,31 @@
 use std::fmt;
 
-#[derive(PartialEq, Eq)]
+use crate::game_result::GameResult;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum State {
     Playing,
-    WhiteIsMated,
-    BlackIsMated,
-    Stalemate,
-    Repitition,
-    FiftyMoveRule,
-    InsufficientMaterial
+    Over(GameResult),
+}
+
+impl State {
+    pub fn is_over(&self) -> bool {
+        *self != State::Playing
+    }
+
+    pub fn result(&self) -> Option<GameResult> {
+        match self {
+            State::Playing => None,
+            State::Over(result) => Some(*result),
+        }
+    }
 }
 
 impl fmt::Display for State {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let string = match self {
-            Self::Playing => "Still playing",
-            Self::WhiteIsMated => "Black won by checkmate",
-            Self::BlackIsMated => "White won by checkmate",
-            Self::Stalemate => "Draw by stalemate",
-            Self::Repitition => "Draw by repition",
-            Self::FiftyMoveRule => "Draw due to fifty move rule",
-            Self::InsufficientMaterial => "Draw due to insufficient material"
-        };
-        write!(f, "{}", string)
+        match self {
+            Self::Playing => write!(f, "Still playing"),
+            Self::Over(result) => write!(f, "{}", result),
+        }
     }
-}
\ No newline at end of file
+}
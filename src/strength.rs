@@ -0,0 +1,109 @@
+use std::fmt;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::bot::Line;
+
+/// playing strength of the engine, from level 1 for beginners to the full strength at level 20.
+/// Weaker levels search less deep with fewer nodes, choose randomly among the moves close to
+/// the best one and sometimes blunder
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Strength {
+    level: u8,
+}
+
+impl Strength {
+    pub const MIN_LEVEL: u8 = 1;
+    pub const MAX_LEVEL: u8 = 20;
+    /// the rough Elo of the weakest and the strongest level
+    pub const MIN_ELO: u32 = 800;
+    pub const MAX_ELO: u32 = 2400;
+
+    pub fn new(level: u8) -> Strength {
+        Strength {
+            level: level.clamp(Strength::MIN_LEVEL, Strength::MAX_LEVEL),
+        }
+    }
+
+    /// the level closest to `elo`
+    pub fn from_elo(elo: u32) -> Strength {
+        let elo = elo.clamp(Strength::MIN_ELO, Strength::MAX_ELO) - Strength::MIN_ELO;
+        let levels = (Strength::MAX_LEVEL - Strength::MIN_LEVEL) as u32;
+        let range = Strength::MAX_ELO - Strength::MIN_ELO;
+        Strength::new(Strength::MIN_LEVEL + ((elo * levels + range / 2) / range) as u8)
+    }
+
+    /// all levels, weakest first
+    pub fn levels() -> impl Iterator<Item = Strength> {
+        (Strength::MIN_LEVEL..=Strength::MAX_LEVEL).map(Strength::new)
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    pub fn elo(&self) -> u32 {
+        let levels = (Strength::MAX_LEVEL - Strength::MIN_LEVEL) as u32;
+        let step = (self.level - Strength::MIN_LEVEL) as u32;
+        Strength::MIN_ELO + step * (Strength::MAX_ELO - Strength::MIN_ELO) / levels
+    }
+
+    fn weakness(&self) -> u8 {
+        Strength::MAX_LEVEL - self.level
+    }
+
+    /// the deepest iteration searched, `None` at full strength
+    pub fn depth(&self) -> Option<i32> {
+        (self.weakness() > 0).then_some((self.level as i32 + 3) / 4)
+    }
+
+    /// the most positions searched, `None` at full strength
+    pub fn nodes(&self) -> Option<u64> {
+        (self.weakness() > 0).then_some(50 << (self.level / 2))
+    }
+
+    /// the number of lines to search to choose from
+    pub fn lines(&self) -> usize {
+        if self.weakness() > 0 {
+            4
+        } else {
+            1
+        }
+    }
+
+    /// centipawns a move may be worse than the best one and still be chosen
+    pub fn margin(&self) -> i32 {
+        self.weakness() as i32 * 10
+    }
+
+    /// the chance to play any of the lines searched regardless of its score
+    pub fn blunder_probability(&self) -> f64 {
+        self.weakness() as f64 / 100.0
+    }
+
+    /// picks one of `lines`, best first as returned by the search
+    pub fn choose<'a, R: Rng>(&self, lines: &'a [Line], rng: &mut R) -> Option<&'a Line> {
+        let best = lines.first()?;
+        if rng.gen_bool(self.blunder_probability()) {
+            return lines.choose(rng);
+        }
+        let close: Vec<&Line> = lines
+            .iter()
+            .filter(|line| line.score >= best.score - self.margin())
+            .collect();
+        close.choose(rng).copied()
+    }
+}
+
+impl Default for Strength {
+    fn default() -> Self {
+        Strength::new(Strength::MAX_LEVEL)
+    }
+}
+
+impl fmt::Display for Strength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Level {} ({} Elo)", self.level, self.elo())
+    }
+}
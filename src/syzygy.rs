@@ -0,0 +1,1291 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::board::Data;
+use crate::{Board, Move};
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+// flags of the compressed values of a table
+const STM: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+
+/// rank of root moves winning within the fifty move rule
+const MAX_DTZ: i32 = 1 << 18;
+
+/// value of a position from the view of the side to move, a cursed win or a blessed loss can
+/// not be forced before the fifty move rule applies
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Wdl {
+        match value {
+            ..=-2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            _ => Wdl::Win,
+        }
+    }
+
+    /// -2 for a loss up to 2 for a win
+    fn value(self) -> i32 {
+        self as i32 - 2
+    }
+}
+
+impl std::ops::Neg for Wdl {
+    type Output = Wdl;
+
+    fn neg(self) -> Wdl {
+        Wdl::from_value(-self.value())
+    }
+}
+
+/// distance to zeroing of a position where the best move is a capture or a pawn move
+fn dtz_before_zeroing(wdl: Wdl) -> i32 {
+    match wdl {
+        Wdl::Loss => -1,
+        Wdl::BlessedLoss => -101,
+        Wdl::Draw => 0,
+        Wdl::CursedWin => 101,
+        Wdl::Win => 1,
+    }
+}
+
+/// maps of squares and binomial coefficients used by the index of the tables
+struct Indices {
+    /// squares below the a1-h8 diagonal to 0..28
+    map_b1h1h7: [u64; 64],
+    /// squares of the a1-d1-d4 triangle to 0..10, the ones on the diagonal last
+    map_a1d1d4: [usize; 64],
+    /// the 462 placements of two kings with the first one in the a1-d1-d4 triangle
+    map_kk: [[u64; 64]; 10],
+    binomial: [[u64; 64]; 6],
+    /// squares a2-h7 to 0..48, higher nearer to the edge and to the second rank
+    map_pawns: [usize; 64],
+    lead_pawn_idx: [[u64; 64]; 6],
+    lead_pawns_size: [[u64; 4]; 6],
+}
+
+static INDICES: Indices = Indices::new();
+
+/// positive above the a1-h8 diagonal, negative below it
+const fn off_diagonal(square: usize) -> i32 {
+    (square / 8) as i32 - (square % 8) as i32
+}
+
+impl Indices {
+    const fn new() -> Indices {
+        let mut indices = Indices {
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; 6],
+            map_pawns: [0; 64],
+            lead_pawn_idx: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+
+        let mut code = 0;
+        let mut square = 0;
+        while square < 64 {
+            if off_diagonal(square) < 0 {
+                indices.map_b1h1h7[square] = code;
+                code += 1;
+            }
+            square += 1;
+        }
+
+        let mut code = 0;
+        let mut pass = 0;
+        while pass < 2 {
+            let mut square = 0;
+            while square <= 27 {
+                let off = off_diagonal(square);
+                if square % 8 <= 3 && off <= 0 && (off == 0) == (pass == 1) {
+                    indices.map_a1d1d4[square] = code;
+                    code += 1;
+                }
+                square += 1;
+            }
+            pass += 1;
+        }
+
+        // placements with both kings on the diagonal come last
+        let mut code = 0;
+        let mut pass = 0;
+        while pass < 2 {
+            let mut idx = 0;
+            while idx < 10 {
+                let mut first = 0;
+                while first <= 27 {
+                    // b1 is the only square of the triangle mapped to 0
+                    if indices.map_a1d1d4[first] == idx && (idx != 0 || first == 1) {
+                        let mut second = 0;
+                        while second < 64 {
+                            let adjacent = (first / 8).abs_diff(second / 8) <= 1
+                                && (first % 8).abs_diff(second % 8) <= 1;
+                            let on_diagonal = off_diagonal(first) == 0;
+                            // the second king below the diagonal if the first one is on it
+                            let above = on_diagonal && off_diagonal(second) > 0;
+                            if !(adjacent || above)
+                                && (on_diagonal && off_diagonal(second) == 0) == (pass == 1)
+                            {
+                                indices.map_kk[idx][second] = code;
+                                code += 1;
+                            }
+                            second += 1;
+                        }
+                    }
+                    first += 1;
+                }
+                idx += 1;
+            }
+            pass += 1;
+        }
+
+        indices.binomial[0][0] = 1;
+        let mut n = 1;
+        while n < 64 {
+            let mut k = 0;
+            while k < 6 && k <= n {
+                let with = if k > 0 {
+                    indices.binomial[k - 1][n - 1]
+                } else {
+                    0
+                };
+                let without = if k < n { indices.binomial[k][n - 1] } else { 0 };
+                indices.binomial[k][n] = with + without;
+                k += 1;
+            }
+            n += 1;
+        }
+
+        let mut available = 48;
+        let mut lead = 1;
+        while lead <= 5 {
+            let mut file = 0;
+            while file < 4 {
+                let mut idx = 0;
+                let mut rank = 1;
+                while rank <= 6 {
+                    let square = rank * 8 + file;
+                    if lead == 1 {
+                        indices.map_pawns[square] = available - 1;
+                        indices.map_pawns[square ^ 7] = available - 2;
+                        available -= 2;
+                    }
+                    indices.lead_pawn_idx[lead][square] = idx;
+                    idx += indices.binomial[lead - 1][indices.map_pawns[square]];
+                    rank += 1;
+                }
+                indices.lead_pawns_size[lead][file] = idx;
+                file += 1;
+            }
+            lead += 1;
+        }
+        indices
+    }
+}
+
+/// the Syzygy code of a piece, 1 to 6 for a white pawn, knight, bishop, rook, queen and king
+/// and 9 to 14 for the black ones
+fn encode_piece(piece: i8) -> u8 {
+    [0, 1, 3, 2, 4, 5, 6][piece.unsigned_abs() as usize] | if piece < 0 { 8 } else { 0 }
+}
+
+/// the pieces of the position in the Syzygy encoding with their squares
+fn pieces(data: &Data) -> Vec<(u8, u8)> {
+    let mut pieces = Vec::new();
+    for piece in [1, 2, 3, 4, 5, 6, -1, -2, -3, -4, -5, -6] {
+        for (_, square) in data.piece_list.from_type(piece) {
+            pieces.push((encode_piece(piece), *square));
+        }
+    }
+    pieces
+}
+
+/// the name of the material of one side, ex.: "KRP", `color` is 0 for white and 8 for black
+fn side_name(pieces: &[(u8, u8)], color: u8) -> String {
+    let mut name = String::new();
+    for (c, code) in [('K', 6), ('Q', 5), ('R', 4), ('B', 3), ('N', 2), ('P', 1)] {
+        for _ in pieces.iter().filter(|(piece, _)| *piece == code | color) {
+            name.push(c);
+        }
+    }
+    name
+}
+
+fn is_capture(data: &Data, _move: &Move) -> bool {
+    _move.en_passant() || (_move.castling() == 0 && !data.board[_move.end as usize].is_empty())
+}
+
+fn is_zeroing(data: &Data, _move: &Move) -> bool {
+    is_capture(data, _move) || data.board[_move.start as usize].is_pawn()
+}
+
+/// the pieces of a table named like "KRPvKR", the side named first is white in the table
+#[derive(Clone, Debug)]
+struct Material {
+    pieces: Vec<u8>,
+    symmetric: bool,
+    has_pawns: bool,
+    has_unique_pieces: bool,
+    /// pawns of the leading color, the one with fewer pawns, and of the other color
+    pawn_count: [usize; 2],
+}
+
+impl Material {
+    fn parse(name: &str) -> Option<Material> {
+        let (white, black) = name.split_once('v')?;
+        let mut pieces = Vec::new();
+        for (side, color) in [(white, 0), (black, 8)] {
+            if side.matches('K').count() != 1 {
+                return None;
+            }
+            for c in side.chars() {
+                let code = match c {
+                    'K' => 6,
+                    'Q' => 5,
+                    'R' => 4,
+                    'B' => 3,
+                    'N' => 2,
+                    'P' => 1,
+                    _ => return None,
+                };
+                pieces.push(code | color);
+            }
+        }
+        let count = |code: u8| pieces.iter().filter(|piece| **piece == code).count();
+        let (white_pawns, black_pawns) = (count(1), count(9));
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        Some(Material {
+            symmetric: white == black,
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces: pieces
+                .iter()
+                .any(|piece| piece & 7 != 6 && count(*piece) == 1),
+            pawn_count: if white_leads {
+                [white_pawns, black_pawns]
+            } else {
+                [black_pawns, white_pawns]
+            },
+            pieces,
+        })
+    }
+}
+
+/// reads the header of a table, `None` if it ends too early
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn u8(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes([
+            self.u8()?,
+            self.u8()?,
+            self.u8()?,
+            self.u8()?,
+        ]))
+    }
+}
+
+fn byte(bytes: &[u8], pos: usize) -> u8 {
+    bytes.get(pos).copied().unwrap_or(0)
+}
+
+fn le_u16(bytes: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([byte(bytes, pos), byte(bytes, pos + 1)])
+}
+
+fn le_u32(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([0, 1, 2, 3].map(|i| byte(bytes, pos + i)))
+}
+
+fn be_u32(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_be_bytes([0, 1, 2, 3].map(|i| byte(bytes, pos + i)))
+}
+
+/// the compressed values of one side to move and one file of the leading pawn, positions in
+/// the bytes of the table are stored instead of slices
+#[derive(Clone, Debug, Default)]
+struct Pairs {
+    flags: u8,
+    /// the order of the pieces in the index
+    pieces: Vec<u8>,
+    /// pieces of each group encoded together
+    group_len: Vec<usize>,
+    /// factor of the index of each group, the last one is the size of the table
+    group_idx: Vec<u64>,
+    block_size: usize,
+    span: u64,
+    sparse_index: usize,
+    block_length: usize,
+    data: usize,
+    blocks: usize,
+    /// the value of all positions if the table stores a single one
+    min_sym_len: u8,
+    lowest_sym: usize,
+    base64: Vec<u64>,
+    symlen: Vec<u8>,
+    btree: usize,
+    /// start of the maps from stored values to distances for each result of DTZ tables
+    map_idx: [usize; 4],
+}
+
+impl Pairs {
+    fn size(&self) -> u64 {
+        self.group_idx[self.group_len.len()]
+    }
+
+    /// splits the pieces into groups and computes the factor of each group in the index, `order`
+    /// is the position of the leading group and of the remaining pawns among the factors
+    fn set_groups(&mut self, material: &Material, order: [u8; 2], file: usize) {
+        let mut first_len = if material.has_pawns {
+            0
+        } else if material.has_unique_pieces {
+            3
+        } else {
+            2
+        };
+        self.group_len = vec![1];
+        for i in 1..self.pieces.len() {
+            first_len -= 1;
+            if first_len > 0 || self.pieces[i] == self.pieces[i - 1] {
+                *self.group_len.last_mut().unwrap() += 1;
+            } else {
+                self.group_len.push(1);
+            }
+        }
+
+        let groups = self.group_len.len();
+        let both_pawns = material.has_pawns && material.pawn_count[1] > 0;
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free_squares =
+            64 - self.group_len[0] - if both_pawns { self.group_len[1] } else { 0 };
+        let mut idx = 1;
+        self.group_idx = vec![0; groups + 1];
+        let mut k = 0;
+        while next < groups || k == order[0] || k == order[1] {
+            if k == order[0] {
+                self.group_idx[0] = idx;
+                idx *= if material.has_pawns {
+                    INDICES.lead_pawns_size[self.group_len[0]][file]
+                } else if material.has_unique_pieces {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] {
+                self.group_idx[1] = idx;
+                idx *= INDICES.binomial[self.group_len[1]][48 - self.group_len[0]];
+            } else {
+                self.group_idx[next] = idx;
+                idx *= INDICES.binomial[self.group_len[next]][free_squares];
+                free_squares -= self.group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+        self.group_idx[groups] = idx;
+    }
+
+    /// reads the block sizes and the canonical Huffman code of the symbols
+    fn set_sizes(&mut self, reader: &mut Reader) -> Option<()> {
+        self.flags = reader.u8()?;
+        if self.flags & SINGLE_VALUE != 0 {
+            self.min_sym_len = reader.u8()?;
+            return Some(());
+        }
+        self.block_size = 1 << reader.u8()?;
+        self.span = 1 << reader.u8()?;
+        let padding = reader.u8()? as usize;
+        self.blocks = reader.u32()? as usize;
+        let max_sym_len = reader.u8()?;
+        self.min_sym_len = reader.u8()?;
+        if max_sym_len < self.min_sym_len || self.min_sym_len == 0 || self.span == 0 {
+            return None;
+        }
+        self.lowest_sym = reader.pos;
+        let lengths = (max_sym_len - self.min_sym_len) as usize + 1;
+        let lowest = |i: usize| le_u16(reader.bytes, self.lowest_sym + 2 * i) as u64;
+
+        // symbols of each length are consecutive, longer codes have lower values
+        self.base64 = vec![0; lengths];
+        for i in (0..lengths - 1).rev() {
+            self.base64[i] = self.base64[i + 1]
+                .wrapping_add(lowest(i))
+                .wrapping_sub(lowest(i + 1))
+                / 2;
+        }
+        for (i, base) in self.base64.iter_mut().enumerate() {
+            *base = base
+                .checked_shl(64 - i as u32 - self.min_sym_len as u32)
+                .unwrap_or(0);
+        }
+        reader.pos += 2 * lengths;
+
+        let symbols = reader.u16()? as usize;
+        self.btree = reader.pos;
+        reader.pos += 3 * symbols + (symbols & 1);
+        if reader.pos > reader.bytes.len() {
+            return None;
+        }
+        self.symlen = vec![0; symbols];
+        let mut visited = vec![false; symbols];
+        for symbol in 0..symbols {
+            if !visited[symbol] {
+                self.symlen[symbol] = self.set_symlen(reader.bytes, symbol, &mut visited)?;
+            }
+        }
+        Some(())
+    }
+
+    /// the number of values a symbol expands to minus one, symbols are pairs of symbols or leaves
+    fn set_symlen(&mut self, bytes: &[u8], symbol: usize, visited: &mut [bool]) -> Option<u8> {
+        visited[symbol] = true;
+        let right = self.right(bytes, symbol) as usize;
+        if right == 0xfff {
+            return Some(0);
+        }
+        let left = self.left(bytes, symbol) as usize;
+        for child in [left, right] {
+            if !*visited.get(child)? {
+                self.symlen[child] = self.set_symlen(bytes, child, visited)?;
+            }
+        }
+        Some(
+            self.symlen[left]
+                .wrapping_add(self.symlen[right])
+                .wrapping_add(1),
+        )
+    }
+
+    fn left(&self, bytes: &[u8], symbol: usize) -> u16 {
+        let lr = self.btree + 3 * symbol;
+        (byte(bytes, lr + 1) as u16 & 0xf) << 8 | byte(bytes, lr) as u16
+    }
+
+    fn right(&self, bytes: &[u8], symbol: usize) -> u16 {
+        let lr = self.btree + 3 * symbol;
+        (byte(bytes, lr + 2) as u16) << 4 | byte(bytes, lr + 1) as u16 >> 4
+    }
+
+    fn block_length(&self, bytes: &[u8], block: usize) -> i64 {
+        le_u16(bytes, self.block_length + 2 * block) as i64
+    }
+
+    /// the value stored at `index`
+    fn decompress(&self, bytes: &[u8], index: u64) -> Option<u16> {
+        if self.flags & SINGLE_VALUE != 0 {
+            return Some(self.min_sym_len as u16);
+        }
+
+        // the sparse index points into the block of the value in the middle of each span
+        let k = (index / self.span) as usize;
+        let mut block = le_u32(bytes, self.sparse_index + 6 * k) as usize;
+        let mut offset = le_u16(bytes, self.sparse_index + 6 * k + 4) as i64;
+        offset += (index % self.span) as i64 - (self.span / 2) as i64;
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += self.block_length(bytes, block) + 1;
+        }
+        while offset > self.block_length(bytes, block) {
+            offset -= self.block_length(bytes, block) + 1;
+            block += 1;
+            if block >= self.blocks {
+                return None;
+            }
+        }
+
+        let mut ptr = self.data + block * self.block_size;
+        let mut buffer = (be_u32(bytes, ptr) as u64) << 32 | be_u32(bytes, ptr + 4) as u64;
+        ptr += 8;
+        let mut buffer_size = 64;
+        let min_sym_len = self.min_sym_len as u32;
+        let mut symbol;
+        loop {
+            let mut len = 0;
+            while buffer < self.base64[len] {
+                len += 1;
+                if len == self.base64.len() {
+                    return None;
+                }
+            }
+            symbol = ((buffer - self.base64[len]) >> (64 - len as u32 - min_sym_len)) as u16;
+            symbol = symbol.wrapping_add(le_u16(bytes, self.lowest_sym + 2 * len));
+            let expanded = *self.symlen.get(symbol as usize)? as i64 + 1;
+            if offset < expanded {
+                break;
+            }
+            offset -= expanded;
+            let len = len as u32 + min_sym_len;
+            buffer = buffer.checked_shl(len).unwrap_or(0);
+            buffer_size -= len;
+            if buffer_size <= 32 {
+                buffer_size += 32;
+                buffer |= (be_u32(bytes, ptr) as u64) << (64 - buffer_size);
+                ptr += 4;
+            }
+        }
+
+        // recursive pairing, descend into the half of the pair holding the value
+        while self.symlen[symbol as usize] != 0 {
+            let left = self.left(bytes, symbol as usize);
+            let expanded = *self.symlen.get(left as usize)? as i64 + 1;
+            if offset < expanded {
+                symbol = left;
+            } else {
+                offset -= expanded;
+                symbol = self.right(bytes, symbol as usize);
+            }
+        }
+        Some(self.left(bytes, symbol as usize))
+    }
+}
+
+/// a WDL or DTZ table of one material
+struct Table {
+    bytes: Vec<u8>,
+    material: Material,
+    dtz: bool,
+    /// for each file of the leading pawn, or only one without pawns, the values with white and
+    /// with black to move, DTZ tables and tables of symmetric material store only one side
+    pairs: Vec<Vec<Pairs>>,
+    /// start of the maps of DTZ tables
+    map: usize,
+}
+
+impl Table {
+    fn parse(bytes: Vec<u8>, material: Material, dtz: bool) -> Option<Table> {
+        let magic = if dtz { DTZ_MAGIC } else { WDL_MAGIC };
+        if bytes.get(..4)? != magic {
+            return None;
+        }
+        let mut table = Table {
+            bytes: Vec::new(),
+            material,
+            dtz,
+            pairs: Vec::new(),
+            map: 0,
+        };
+        let mut reader = Reader {
+            bytes: &bytes,
+            pos: 4,
+        };
+        let flags = reader.u8()?;
+        if (flags & 2 != 0) != table.material.has_pawns {
+            return None;
+        }
+        let sides = if dtz || table.material.symmetric {
+            1
+        } else {
+            2
+        };
+        let files = if table.material.has_pawns { 4 } else { 1 };
+        let both_pawns = table.material.has_pawns && table.material.pawn_count[1] > 0;
+
+        for file in 0..files {
+            let first = reader.u8()?;
+            let second = if both_pawns { reader.u8()? } else { 0xff };
+            let mut pairs = vec![Pairs::default(); sides];
+            for _ in 0..table.material.pieces.len() {
+                let byte = reader.u8()?;
+                for (side, pairs) in pairs.iter_mut().enumerate() {
+                    pairs
+                        .pieces
+                        .push(if side == 0 { byte & 0xf } else { byte >> 4 });
+                }
+            }
+            for (side, pairs) in pairs.iter_mut().enumerate() {
+                let order = if side == 0 {
+                    [first & 0xf, second & 0xf]
+                } else {
+                    [first >> 4, second >> 4]
+                };
+                pairs.set_groups(&table.material, order, file);
+            }
+            table.pairs.push(pairs);
+        }
+        reader.pos += reader.pos & 1;
+
+        for pairs in table.pairs.iter_mut().flatten() {
+            pairs.set_sizes(&mut reader)?;
+        }
+
+        if dtz {
+            table.map = reader.pos;
+            for pairs in table.pairs.iter_mut().flatten() {
+                if pairs.flags & MAPPED == 0 {
+                    continue;
+                }
+                if pairs.flags & WIDE != 0 {
+                    reader.pos += reader.pos & 1;
+                    for i in 0..4 {
+                        pairs.map_idx[i] = (reader.pos - table.map) / 2 + 1;
+                        reader.pos += 2 * reader.u16()? as usize;
+                    }
+                } else {
+                    for i in 0..4 {
+                        pairs.map_idx[i] = reader.pos - table.map + 1;
+                        reader.pos += reader.u8()? as usize;
+                    }
+                }
+            }
+            reader.pos += reader.pos & 1;
+        }
+
+        for pairs in table.pairs.iter_mut().flatten() {
+            pairs.sparse_index = reader.pos;
+            if pairs.flags & SINGLE_VALUE == 0 {
+                reader.pos += 6 * pairs.size().div_ceil(pairs.span) as usize;
+            }
+        }
+        for pairs in table.pairs.iter_mut().flatten() {
+            pairs.block_length = reader.pos;
+            if pairs.flags & SINGLE_VALUE == 0 {
+                let padding = byte(&bytes, pairs.lowest_sym - 7) as usize;
+                reader.pos += 2 * (pairs.blocks + padding);
+            }
+        }
+        for pairs in table.pairs.iter_mut().flatten() {
+            reader.pos = (reader.pos + 0x3f) & !0x3f;
+            pairs.data = reader.pos;
+            reader.pos += pairs.blocks * pairs.block_size;
+        }
+        if reader.pos > bytes.len() {
+            return None;
+        }
+        table.bytes = bytes;
+        Some(table)
+    }
+
+    /// the file, side and index the value of a position is stored at, `None` if this is a DTZ
+    /// table storing the other side to move. `black_stronger` is set if the colors of the
+    /// position are swapped compared to the table
+    fn entry(
+        &self,
+        pieces: &[(u8, u8)],
+        black_to_move: bool,
+        black_stronger: bool,
+    ) -> Option<(usize, usize, u64)> {
+        let material = &self.material;
+        // tables of symmetric material only store white to move
+        let flip = black_stronger || (material.symmetric && black_to_move);
+        let (flip_color, flip_squares) = if flip { (8, 56) } else { (0, 0) };
+        let side = (flip != black_to_move) as usize;
+
+        let mut codes = Vec::with_capacity(pieces.len());
+        let mut squares: Vec<usize> = Vec::with_capacity(pieces.len());
+        let mut file = 0;
+        // the pawns of the leading color are at the start of the pieces of every file
+        let lead_pawn = material
+            .has_pawns
+            .then(|| self.pairs[0][0].pieces[0] ^ flip_color);
+        if let Some(lead_pawn) = lead_pawn {
+            for (piece, square) in pieces.iter().filter(|(piece, _)| *piece == lead_pawn) {
+                codes.push(piece ^ flip_color);
+                squares.push((square ^ flip_squares) as usize);
+            }
+            let lead = (1..squares.len()).fold(0, |lead, i| {
+                if INDICES.map_pawns[squares[i]] > INDICES.map_pawns[squares[lead]] {
+                    i
+                } else {
+                    lead
+                }
+            });
+            squares.swap(0, lead);
+            file = (squares[0] % 8).min(7 - squares[0] % 8);
+        }
+        let lead_pawns = squares.len();
+
+        if self.dtz {
+            let stored = (self.pairs[file][0].flags & STM) as usize;
+            if stored != side && (material.has_pawns || !material.symmetric) {
+                return None;
+            }
+        }
+        let pairs = &self.pairs[file][if self.pairs[file].len() == 1 { 0 } else { side }];
+
+        for (piece, square) in pieces.iter().filter(|(piece, _)| Some(*piece) != lead_pawn) {
+            codes.push(piece ^ flip_color);
+            squares.push((square ^ flip_squares) as usize);
+        }
+        let size = squares.len();
+
+        // the order of the pieces in the table
+        for i in lead_pawns..size.saturating_sub(1) {
+            for j in i + 1..size {
+                if pairs.pieces[i] == codes[j] {
+                    codes.swap(i, j);
+                    squares.swap(i, j);
+                    break;
+                }
+            }
+        }
+
+        // the leading piece on files a to d
+        if squares[0] % 8 > 3 {
+            for square in &mut squares {
+                *square ^= 7;
+            }
+        }
+
+        let mut idx;
+        if material.has_pawns {
+            idx = INDICES.lead_pawn_idx[lead_pawns][squares[0]];
+            squares[1..lead_pawns].sort_by_key(|square| INDICES.map_pawns[*square]);
+            for (i, square) in squares.iter().enumerate().take(lead_pawns).skip(1) {
+                idx += INDICES.binomial[i][INDICES.map_pawns[*square]];
+            }
+        } else {
+            // the leading piece on ranks 1 to 4 and the first piece off the diagonal below it
+            if squares[0] / 8 > 3 {
+                for square in &mut squares {
+                    *square ^= 56;
+                }
+            }
+            for i in 0..pairs.group_len[0] {
+                let off = off_diagonal(squares[i]);
+                if off == 0 {
+                    continue;
+                }
+                if off > 0 {
+                    for square in &mut squares[i..] {
+                        *square = ((*square >> 3) | (*square << 3)) & 63;
+                    }
+                }
+                break;
+            }
+
+            let rank = |square: usize| (square / 8) as u64;
+            if material.has_unique_pieces {
+                let adjust1 = (squares[1] > squares[0]) as u64;
+                let adjust2 = (squares[2] > squares[0]) as u64 + (squares[2] > squares[1]) as u64;
+                idx = if off_diagonal(squares[0]) != 0 {
+                    (INDICES.map_a1d1d4[squares[0]] as u64 * 63 + squares[1] as u64 - adjust1) * 62
+                        + squares[2] as u64
+                        - adjust2
+                } else if off_diagonal(squares[1]) != 0 {
+                    (6 * 63 + rank(squares[0]) * 28 + INDICES.map_b1h1h7[squares[1]]) * 62
+                        + squares[2] as u64
+                        - adjust2
+                } else if off_diagonal(squares[2]) != 0 {
+                    6 * 63 * 62
+                        + 4 * 28 * 62
+                        + rank(squares[0]) * 7 * 28
+                        + (rank(squares[1]) - adjust1) * 28
+                        + INDICES.map_b1h1h7[squares[2]]
+                } else {
+                    6 * 63 * 62
+                        + 4 * 28 * 62
+                        + 4 * 7 * 28
+                        + rank(squares[0]) * 7 * 6
+                        + (rank(squares[1]) - adjust1) * 6
+                        + (rank(squares[2]) - adjust2)
+                };
+            } else {
+                idx = INDICES.map_kk[INDICES.map_a1d1d4[squares[0]]][squares[1]];
+            }
+        }
+
+        // the remaining groups, each as a combination of the squares left by the groups before
+        idx *= pairs.group_idx[0];
+        let mut start = pairs.group_len[0];
+        let mut remaining_pawns = material.has_pawns && material.pawn_count[1] > 0;
+        for next in 1..pairs.group_len.len() {
+            let len = pairs.group_len[next];
+            squares[start..start + len].sort_unstable();
+            let mut n = 0;
+            for i in 0..len {
+                let square = squares[start + i];
+                let adjust = squares[..start].iter().filter(|s| square > **s).count();
+                n += INDICES.binomial[i + 1][square - adjust - 8 * remaining_pawns as usize];
+            }
+            remaining_pawns = false;
+            idx += n * pairs.group_idx[next];
+            start += len;
+        }
+        Some((file, side.min(self.pairs[file].len() - 1), idx))
+    }
+
+    /// the distance to zeroing in plies of a value stored in a DTZ table
+    fn dtz_plies(&self, file: usize, value: u16, wdl: Wdl) -> i32 {
+        // indexed by the result, the maps are stored for win, loss, cursed win and blessed loss
+        const MAPS: [usize; 5] = [1, 3, 0, 2, 0];
+        let pairs = &self.pairs[file][0];
+        let mut value = value as i32;
+        if pairs.flags & MAPPED != 0 {
+            let i = pairs.map_idx[MAPS[wdl as usize]] + value as usize;
+            value = if pairs.flags & WIDE != 0 {
+                le_u16(&self.bytes, self.map + 2 * i) as i32
+            } else {
+                byte(&self.bytes, self.map + i) as i32
+            };
+        }
+        let in_moves = match wdl {
+            Wdl::Win => pairs.flags & WIN_PLIES == 0,
+            Wdl::Loss => pairs.flags & LOSS_PLIES == 0,
+            Wdl::CursedWin | Wdl::BlessedLoss => true,
+            Wdl::Draw => false,
+        };
+        if in_moves {
+            value *= 2;
+        }
+        value + 1
+    }
+}
+
+/// a table file, read when a position is first looked up in it
+struct TableFile {
+    path: PathBuf,
+    material: Material,
+    dtz: bool,
+    table: OnceLock<Option<Table>>,
+}
+
+impl TableFile {
+    fn table(&self) -> Option<&Table> {
+        self.table
+            .get_or_init(|| {
+                let bytes = std::fs::read(&self.path).ok()?;
+                Table::parse(bytes, self.material.clone(), self.dtz)
+            })
+            .as_ref()
+    }
+}
+
+/// the value of a position in a DTZ table
+enum Stored {
+    Plies(i32),
+    /// the table stores the positions with the other side to move
+    OtherSide,
+}
+
+/// Syzygy endgame tables, win draw loss (.rtbw) and distance to zeroing (.rtbz) files
+#[derive(Default)]
+pub struct Syzygy {
+    wdl: HashMap<String, TableFile>,
+    dtz: HashMap<String, TableFile>,
+    max_pieces: usize,
+}
+
+impl Syzygy {
+    pub fn new() -> Syzygy {
+        Syzygy::default()
+    }
+
+    /// finds the tables in the directories of `paths`, separated like in the PATH variable, the
+    /// tables are read when they are first needed
+    pub fn load<P: AsRef<OsStr>>(paths: P) -> io::Result<Syzygy> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut syzygy = Syzygy::new();
+        for dir in std::env::split_paths(&paths) {
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                let dtz = match path.extension().and_then(OsStr::to_str) {
+                    Some("rtbw") => false,
+                    Some("rtbz") => true,
+                    _ => continue,
+                };
+                let name = path
+                    .file_stem()
+                    .and_then(OsStr::to_str)
+                    .unwrap_or_default()
+                    .to_string();
+                let material = Material::parse(&name)
+                    .ok_or_else(|| invalid(format!("invalid material {}", name)))?;
+                let mut magic = [0; 4];
+                File::open(&path)?.read_exact(&mut magic)?;
+                if magic != if dtz { DTZ_MAGIC } else { WDL_MAGIC } {
+                    return Err(invalid(format!("{} is not a Syzygy table", path.display())));
+                }
+
+                let tables = if dtz {
+                    &mut syzygy.dtz
+                } else {
+                    syzygy.max_pieces = syzygy.max_pieces.max(material.pieces.len());
+                    &mut syzygy.wdl
+                };
+                tables.insert(
+                    name,
+                    TableFile {
+                        path,
+                        material,
+                        dtz,
+                        table: OnceLock::new(),
+                    },
+                );
+            }
+        }
+        Ok(syzygy)
+    }
+
+    /// the largest number of pieces of the WDL tables found
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// whether the position may be in the tables, the tables do not know castling rights
+    fn covers(&self, board: &Board) -> bool {
+        board.data.not_able_to_castle == 0x0f
+            && board
+                .data
+                .board
+                .iter()
+                .filter(|piece| !piece.is_empty())
+                .count()
+                <= self.max_pieces
+    }
+
+    /// the value of the position, `None` if a table needed is missing or castling is possible
+    pub fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        if !self.covers(board) {
+            return None;
+        }
+        self.search(&mut board.clone(), false).map(|(wdl, _)| wdl)
+    }
+
+    /// the distance in plies to the next capture or pawn move with the best play, or to mate,
+    /// positive if the side to move wins and 0 for draws
+    pub fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        if !self.covers(board) {
+            return None;
+        }
+        self.dtz(&mut board.clone())
+    }
+
+    /// the value of the position and the moves keeping the best value reachable within the
+    /// fifty move rule, winning moves sorted by the fastest progress and losing ones by the
+    /// longest resistance
+    pub fn root_moves(&self, board: &Board) -> Option<(Wdl, Vec<Move>)> {
+        if !self.covers(board) {
+            return None;
+        }
+        let mut board = board.clone();
+        let (wdl, _) = self.search(&mut board, false)?;
+        let counter = board.fifty_move_counter() as i32;
+
+        let mut ranked = Vec::new();
+        for _move in board.legal_moves() {
+            board.make_move(&_move);
+            let dtz = if board.fifty_move_counter() == 0 {
+                self.search(&mut board, false)
+                    .map(|(wdl, _)| dtz_before_zeroing(-wdl))
+            } else {
+                self.dtz(&mut board).map(|dtz| -dtz - dtz.signum())
+            };
+            let mates = dtz == Some(2) && board.legal_moves().is_empty() && board.is_check();
+            board.undo_move();
+            let dtz = if mates { 1 } else { dtz? };
+
+            let rank = match dtz {
+                1.. if dtz + counter <= 99 => MAX_DTZ,
+                1.. => MAX_DTZ - (dtz + counter),
+                ..=-1 if -dtz * 2 + counter < 100 => -MAX_DTZ,
+                ..=-1 => -MAX_DTZ + (-dtz + counter),
+                0 => 0,
+            };
+            ranked.push((_move, rank, dtz));
+        }
+
+        let best = ranked.iter().map(|(_, rank, _)| *rank).max()?;
+        ranked.retain(|(_, rank, _)| *rank == best);
+        ranked.sort_by_key(|(_, _, dtz)| *dtz);
+        Some((wdl, ranked.into_iter().map(|(_move, _, _)| _move).collect()))
+    }
+
+    /// the table of the material of `pieces` and whether its colors are swapped in the table
+    fn table(&self, dtz: bool, pieces: &[(u8, u8)]) -> Option<(&Table, bool)> {
+        let tables = if dtz { &self.dtz } else { &self.wdl };
+        let white = side_name(pieces, 0);
+        let black = side_name(pieces, 8);
+        if let Some(file) = tables.get(&format!("{}v{}", white, black)) {
+            return Some((file.table()?, false));
+        }
+        let file = tables.get(&format!("{}v{}", black, white))?;
+        Some((file.table()?, true))
+    }
+
+    fn probe_wdl_table(&self, board: &Board) -> Option<Wdl> {
+        let pieces = pieces(&board.data);
+        if pieces.len() == 2 {
+            return Some(Wdl::Draw);
+        }
+        let (table, black_stronger) = self.table(false, &pieces)?;
+        let (file, side, index) = table.entry(&pieces, !board.data.white_turn, black_stronger)?;
+        let value = table.pairs[file][side].decompress(&table.bytes, index)?;
+        Some(Wdl::from_value(value as i32 - 2))
+    }
+
+    fn probe_dtz_table(&self, board: &Board, wdl: Wdl) -> Option<Stored> {
+        let pieces = pieces(&board.data);
+        let (table, black_stronger) = self.table(true, &pieces)?;
+        match table.entry(&pieces, !board.data.white_turn, black_stronger) {
+            Some((file, side, index)) => {
+                let value = table.pairs[file][side].decompress(&table.bytes, index)?;
+                Some(Stored::Plies(table.dtz_plies(file, value, wdl)))
+            }
+            None => Some(Stored::OtherSide),
+        }
+    }
+
+    /// the value of the position, searching captures as positions with en passant rights are
+    /// not stored and the value after a capture may not be. Also returns whether a capture, or
+    /// a pawn move if `zeroing` is set, reaches the value
+    fn search(&self, board: &mut Board, zeroing: bool) -> Option<(Wdl, bool)> {
+        let moves = board.legal_moves();
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+        for _move in &moves {
+            let searched_move = if zeroing {
+                is_zeroing(&board.data, _move)
+            } else {
+                is_capture(&board.data, _move)
+            };
+            if !searched_move {
+                continue;
+            }
+            searched += 1;
+            board.make_move(_move);
+            let value = self.search(board, false);
+            board.undo_move();
+            let value = -value?.0;
+            if value > best {
+                best = value;
+                if value == Wdl::Win {
+                    return Some((value, true));
+                }
+            }
+        }
+
+        let all_searched = searched > 0 && searched == moves.len();
+        let value = if all_searched {
+            best
+        } else {
+            self.probe_wdl_table(board)?
+        };
+        // the table stores any value for positions where a capture is best
+        if best >= value {
+            Some((best, best > Wdl::Draw || all_searched))
+        } else {
+            Some((value, false))
+        }
+    }
+
+    fn dtz(&self, board: &mut Board) -> Option<i32> {
+        let (wdl, zeroing) = self.search(board, true)?;
+        if wdl == Wdl::Draw {
+            return Some(0);
+        }
+        if zeroing {
+            return Some(dtz_before_zeroing(wdl));
+        }
+        let sign = wdl.value().signum();
+        if let Stored::Plies(plies) = self.probe_dtz_table(board, wdl)? {
+            let cursed = matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss);
+            return Some((plies + if cursed { 100 } else { 0 }) * sign);
+        }
+
+        // the table stores the other side to move, find the best move one ply deeper
+        let mut best = None;
+        for _move in board.legal_moves() {
+            let zeroing = is_zeroing(&board.data, &_move);
+            board.make_move(&_move);
+            let dtz = if zeroing {
+                self.search(board, false)
+                    .map(|(wdl, _)| -dtz_before_zeroing(wdl))
+            } else {
+                self.dtz(board).map(|dtz| -dtz)
+            };
+            let mates = dtz == Some(1) && board.legal_moves().is_empty() && board.is_check();
+            board.undo_move();
+            let mut dtz = dtz?;
+
+            if mates {
+                best = Some(1);
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz.signum() == sign && best.is_none_or(|best| dtz < best) {
+                best = Some(dtz);
+            }
+        }
+        // without a legal move the side to move is mated
+        Some(best.unwrap_or(-1))
+    }
+}
+
+/// writes a table in the Syzygy format with fixed length codes, to test probing without the
+/// published tables. `positions` are the legal positions with white being the side named first
+/// in `material`, with the value of a WDL table, the result from 0 for a loss to 4 for a win,
+/// or the plies to zeroing minus one with white to move of a DTZ table
+#[cfg(test)]
+pub(crate) fn write_table<P: AsRef<std::path::Path>>(
+    path: P,
+    material: &str,
+    dtz: bool,
+    positions: impl Iterator<Item = (Vec<(i8, u8)>, bool, u16)>,
+) -> io::Result<()> {
+    let material = Material::parse(material).expect("valid material");
+    let both_pawns = material.has_pawns && material.pawn_count[1] > 0;
+    let sides = if dtz || material.symmetric { 1 } else { 2 };
+    let files = if material.has_pawns { 4 } else { 1 };
+
+    // the leading pawns or unique pieces first, equal pieces next to each other
+    let count = |code: &u8| {
+        material
+            .pieces
+            .iter()
+            .filter(|piece| *piece == code)
+            .count()
+    };
+    let mut order = material.pieces.clone();
+    if material.has_pawns {
+        let white_leads = material.pawn_count[0] == count(&1) && count(&1) > 0;
+        let lead = if white_leads { 1 } else { 9 };
+        order.sort_by_key(|piece| (*piece != lead, *piece & 7 != 1, *piece));
+    } else if material.has_unique_pieces {
+        order.sort_by_key(|piece| (count(piece), *piece));
+    } else {
+        order.sort_by_key(|piece| (*piece & 7 != 6, *piece));
+    }
+
+    let mut table = Table {
+        bytes: Vec::new(),
+        material,
+        dtz,
+        pairs: Vec::new(),
+        map: 0,
+    };
+    for file in 0..files {
+        let mut pairs = Pairs {
+            flags: if dtz { WIN_PLIES | LOSS_PLIES } else { 0 },
+            pieces: order.clone(),
+            ..Pairs::default()
+        };
+        pairs.set_groups(&table.material, [0, if both_pawns { 1 } else { 0xf }], file);
+        table.pairs.push(vec![pairs; sides]);
+    }
+
+    let mut values: Vec<Vec<u16>> = table
+        .pairs
+        .iter()
+        .flatten()
+        .map(|pairs| vec![if dtz { 0 } else { 2 }; pairs.size() as usize])
+        .collect();
+    for (pieces, white_turn, value) in positions {
+        let pieces: Vec<(u8, u8)> = pieces
+            .iter()
+            .map(|(piece, square)| (encode_piece(*piece), *square))
+            .collect();
+        if let Some((file, side, index)) = table.entry(&pieces, !white_turn, false) {
+            values[file * sides + side][index as usize] = value;
+        }
+    }
+
+    let mut bytes = if dtz { DTZ_MAGIC } else { WDL_MAGIC }.to_vec();
+    bytes.push(!table.material.symmetric as u8 | (table.material.has_pawns as u8) << 1);
+    for _ in 0..files {
+        bytes.push(0);
+        if both_pawns {
+            bytes.push(0x11);
+        }
+        bytes.extend(order.iter().map(|piece| piece | piece << 4));
+    }
+    bytes.resize(bytes.len() + (bytes.len() & 1), 0);
+
+    // every value in a leaf symbol of the same length, blocks of 32 bytes and spans of 64 values
+    const BLOCK_SIZE: usize = 32;
+    const SPAN: usize = 64;
+    let mut sections = [Vec::new(), Vec::new(), Vec::new()];
+    let mut blocks = Vec::new();
+    for values in &values {
+        let mut symbols = values.clone();
+        symbols.sort_unstable();
+        symbols.dedup();
+        let flags = table.pairs[0][0].flags;
+        if symbols.len() == 1 {
+            bytes.extend([flags | SINGLE_VALUE, symbols[0] as u8]);
+            blocks.push(Vec::new());
+            continue;
+        }
+        let bits = (usize::BITS - (symbols.len() - 1).leading_zeros()) as usize;
+        let per_block = BLOCK_SIZE * 8 / bits;
+        let count = values.len().div_ceil(per_block);
+        bytes.extend([
+            flags,
+            BLOCK_SIZE.trailing_zeros() as u8,
+            SPAN.trailing_zeros() as u8,
+            0,
+        ]);
+        bytes.extend((count as u32).to_le_bytes());
+        bytes.extend([bits as u8, bits as u8, 0, 0]);
+        bytes.extend((symbols.len() as u16).to_le_bytes());
+        for symbol in &symbols {
+            bytes.extend([*symbol as u8, 0xf0 | (*symbol >> 8) as u8, 0xff]);
+        }
+        bytes.resize(bytes.len() + (symbols.len() & 1), 0);
+
+        for k in 0..values.len().div_ceil(SPAN) {
+            let middle = k * SPAN + SPAN / 2;
+            let block = (middle / per_block).min(count - 1);
+            sections[0].extend((block as u32).to_le_bytes());
+            sections[0].extend(((middle - block * per_block) as u16).to_le_bytes());
+        }
+        let mut data = vec![0u8; count * BLOCK_SIZE];
+        for (block, chunk) in values.chunks(per_block).enumerate() {
+            sections[1].extend((chunk.len() as u16 - 1).to_le_bytes());
+            for (i, value) in chunk.iter().enumerate() {
+                let symbol = symbols.binary_search(value).unwrap();
+                for bit in 0..bits {
+                    if symbol >> (bits - 1 - bit) & 1 != 0 {
+                        let pos = block * BLOCK_SIZE * 8 + i * bits + bit;
+                        data[pos / 8] |= 0x80 >> (pos % 8);
+                    }
+                }
+            }
+        }
+        blocks.push(data);
+    }
+    if dtz {
+        bytes.resize(bytes.len() + (bytes.len() & 1), 0);
+    }
+    bytes.append(&mut sections[0]);
+    bytes.append(&mut sections[1]);
+    for data in blocks {
+        bytes.resize((bytes.len() + 0x3f) & !0x3f, 0);
+        bytes.extend(data);
+    }
+    std::fs::write(path, bytes)
+}
@@ -0,0 +1,641 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use crate::board::Data;
+use crate::move_generator::MoveGenerator;
+use crate::{Board, Move, Piece, PieceColor, PieceType};
+
+/// tables are generated for at most this many pieces including the kings
+pub const MAX_PIECES: usize = 4;
+
+/// value of a position from the view of the side to move
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Probe {
+    /// the side to move mates in this many plies
+    Win(u8),
+    Draw,
+    /// the side to move is mated after this many plies, 0 if it is checkmated
+    Loss(u8),
+}
+
+impl Probe {
+    /// the value of the position before the move leading to a position with this value
+    fn parent(self) -> Probe {
+        match self {
+            Probe::Win(plies) => Probe::Loss(plies.saturating_add(1)),
+            Probe::Draw => Probe::Draw,
+            Probe::Loss(plies) => Probe::Win(plies.saturating_add(1)),
+        }
+    }
+
+    /// orders values from the view of the side to move, a faster win and a slower loss are better
+    fn rank(self) -> i32 {
+        match self {
+            Probe::Win(plies) => 1000 - plies as i32,
+            Probe::Draw => 0,
+            Probe::Loss(plies) => plies as i32 - 1000,
+        }
+    }
+
+    // stored as one byte: 0 draw, 1..=128 loss in 0..=127 plies, 129..=255 win in 1..=127 plies
+    fn to_byte(self) -> u8 {
+        match self {
+            Probe::Draw => 0,
+            Probe::Loss(plies) => plies.min(127) + 1,
+            Probe::Win(plies) => plies.min(127) + 128,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Probe {
+        match byte {
+            0 => Probe::Draw,
+            1..=128 => Probe::Loss(byte - 1),
+            _ => Probe::Win(byte - 128),
+        }
+    }
+}
+
+/// pieces in the order used by the index of a table: white before black, within a color kings,
+/// queens, rooks, bishops, knights and pawns
+type Pieces = Vec<(i8, u8)>;
+
+const ORDER: [char; 6] = ['K', 'Q', 'R', 'B', 'N', 'P'];
+
+fn piece_char(piece: i8) -> char {
+    ORDER[[0, 5, 3, 4, 2, 1, 0][piece.unsigned_abs() as usize]]
+}
+
+fn sort_pieces(pieces: &mut Pieces) {
+    pieces.sort_by_key(|(piece, square)| {
+        let order = ORDER.iter().position(|c| *c == piece_char(*piece));
+        (*piece < 0, order, *square)
+    });
+}
+
+/// the name of the material of one side, ex.: "KRP"
+fn side_material(pieces: &Pieces, white: bool) -> String {
+    pieces
+        .iter()
+        .filter(|(piece, _)| (*piece > 0) == white)
+        .map(|(piece, _)| piece_char(*piece))
+        .collect()
+}
+
+fn material_value(material: &str) -> i32 {
+    material
+        .chars()
+        .map(|c| match c {
+            'Q' => 9,
+            'R' => 5,
+            'B' | 'N' => 3,
+            'P' => 1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// whether the material with white and black swapped is the one a table is stored for, the
+/// stronger side is always white
+fn is_mirrored(white: &str, black: &str) -> bool {
+    (material_value(black), black.len(), black) > (material_value(white), white.len(), white)
+}
+
+/// the name of the table of the material with the stronger side first, ex.: "KPvK"
+fn material_name(pieces: &Pieces) -> String {
+    let white = side_material(pieces, true);
+    let black = side_material(pieces, false);
+    if is_mirrored(&white, &black) {
+        format!("{}v{}", black, white)
+    } else {
+        format!("{}v{}", white, black)
+    }
+}
+
+/// positions without any mate, which do not need a table
+fn is_dead_draw(pieces: &Pieces) -> bool {
+    pieces.len() == 2
+        || (pieces.len() == 3 && pieces.iter().any(|(piece, _)| matches!(piece.abs(), 2 | 3)))
+}
+
+/// swaps the colors and mirrors the board vertically
+fn mirror(pieces: &Pieces) -> Pieces {
+    let mut pieces: Pieces = pieces
+        .iter()
+        .map(|(piece, square)| (-piece, square ^ 56))
+        .collect();
+    sort_pieces(&mut pieces);
+    pieces
+}
+
+/// win, draw or loss and distance to mate of every position with a given material
+pub struct Table {
+    material: String,
+    pieces: Vec<i8>,
+    values: Vec<u8>,
+}
+
+impl Table {
+    fn size(pieces: usize) -> usize {
+        2 << (6 * pieces)
+    }
+
+    /// parses material like "KPvK" into the pieces of the table
+    fn parse_material(material: &str) -> Result<Vec<i8>, String> {
+        let invalid = || format!("invalid material {}", material);
+        let (white, black) = material.split_once('v').ok_or_else(invalid)?;
+        let mut pieces = Pieces::new();
+        for (side, color) in [(white, 1), (black, -1)] {
+            if side.matches('K').count() != 1 {
+                return Err(invalid());
+            }
+            for c in side.chars() {
+                let piece = match c {
+                    'K' => 6,
+                    'Q' => 5,
+                    'R' => 4,
+                    'B' => 2,
+                    'N' => 3,
+                    'P' => 1,
+                    _ => return Err(invalid()),
+                };
+                pieces.push((piece * color, 0));
+            }
+        }
+        if pieces.len() > MAX_PIECES {
+            return Err(format!("{} has more than {} pieces", material, MAX_PIECES));
+        }
+        sort_pieces(&mut pieces);
+        if material_name(&pieces) != material {
+            return Err(format!(
+                "{} is stored as {}",
+                material,
+                material_name(&pieces)
+            ));
+        }
+        Ok(pieces.into_iter().map(|(piece, _)| piece).collect())
+    }
+
+    pub fn material(&self) -> &str {
+        &self.material
+    }
+
+    fn index(squares: impl Iterator<Item = u8>, white_turn: bool) -> usize {
+        squares.fold(0, |index, square| index << 6 | square as usize) << 1 | !white_turn as usize
+    }
+
+    fn squares(&self, index: usize) -> (Vec<u8>, bool) {
+        let n = self.pieces.len();
+        let squares = (0..n)
+            .map(|i| ((index >> (1 + 6 * (n - 1 - i))) & 63) as u8)
+            .collect();
+        (squares, index & 1 == 0)
+    }
+
+    fn probe(&self, pieces: &Pieces, white_turn: bool) -> Probe {
+        let index = Table::index(pieces.iter().map(|(_, square)| *square), white_turn);
+        Probe::from_byte(self.values[index])
+    }
+}
+
+/// endgame tables generated by retrograde analysis
+#[derive(Default)]
+pub struct Tablebases {
+    tables: HashMap<String, Table>,
+}
+
+// states of the positions during generation
+const VALID: u8 = 1;
+const RESOLVED: u8 = 2;
+/// a move into another table draws or wins, so the position is never lost
+const NOT_LOST: u8 = 4;
+
+impl Tablebases {
+    pub fn new() -> Tablebases {
+        Tablebases::default()
+    }
+
+    pub fn table(&self, material: &str) -> Option<&Table> {
+        self.tables.get(material)
+    }
+
+    pub fn materials(&self) -> impl Iterator<Item = &str> + '_ {
+        self.tables.keys().map(String::as_str)
+    }
+
+    /// the largest number of pieces of the loaded tables
+    pub fn max_pieces(&self) -> usize {
+        self.tables
+            .values()
+            .map(|table| table.pieces.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// the value of the position, `None` if there is no table for it or castling or en passant
+    /// is possible
+    pub fn probe(&self, board: &Board) -> Option<Probe> {
+        let data = &board.data;
+        if data.not_able_to_castle != 0x0f {
+            return None;
+        }
+        let mut pieces: Pieces = data
+            .board
+            .iter()
+            .enumerate()
+            .filter(|(_, piece)| !piece.is_empty())
+            .map(|(square, piece)| (piece.piece, square as u8))
+            .collect();
+        if pieces.len() > MAX_PIECES
+            || (data.two_square_advance != 0 && board.legal_moves().iter().any(Move::en_passant))
+        {
+            return None;
+        }
+        sort_pieces(&mut pieces);
+        self.probe_pieces(&pieces, data.white_turn)
+    }
+
+    fn probe_pieces(&self, pieces: &Pieces, white_turn: bool) -> Option<Probe> {
+        if is_dead_draw(pieces) {
+            return Some(Probe::Draw);
+        }
+        let table = self.tables.get(&material_name(pieces))?;
+        if is_mirrored(&side_material(pieces, true), &side_material(pieces, false)) {
+            Some(table.probe(&mirror(pieces), !white_turn))
+        } else {
+            Some(table.probe(pieces, white_turn))
+        }
+    }
+
+    /// the move keeping the best value, the fastest win or the slowest loss
+    pub fn best_move(&self, board: &Board) -> Option<(Move, Probe)> {
+        self.probe(board)?;
+        let mut best: Option<(Move, Probe)> = None;
+        for _move in board.legal_moves() {
+            let mut child = board.clone();
+            child.make_move(&_move);
+            let value = if child.legal_moves().is_empty() {
+                if child.is_check() {
+                    Probe::Win(1)
+                } else {
+                    Probe::Draw
+                }
+            } else {
+                self.probe(&child)?.parent()
+            };
+            if best
+                .as_ref()
+                .is_none_or(|(_, best)| value.rank() > best.rank())
+            {
+                best = Some((_move, value));
+            }
+        }
+        best
+    }
+
+    /// writes every table to `<material>.tb` in `dir`
+    pub fn save<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        std::fs::create_dir_all(&dir)?;
+        for table in self.tables.values() {
+            let path = dir.as_ref().join(format!("{}.tb", table.material));
+            std::fs::write(path, &table.values)?;
+        }
+        Ok(())
+    }
+
+    /// reads all tables saved in `dir`
+    pub fn load<P: AsRef<Path>>(dir: P) -> io::Result<Tablebases> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut tablebases = Tablebases::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "tb") {
+                continue;
+            }
+            let material = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default()
+                .to_string();
+            let pieces = Table::parse_material(&material).map_err(invalid)?;
+            let values = std::fs::read(&path)?;
+            if values.len() != Table::size(pieces.len()) {
+                return Err(invalid(format!("{} has the wrong size", path.display())));
+            }
+            tablebases.tables.insert(
+                material.clone(),
+                Table {
+                    material,
+                    pieces,
+                    values,
+                },
+            );
+        }
+        Ok(tablebases)
+    }
+
+    /// generates the table of `material`, ex.: "KPvK", and all tables reachable from it by
+    /// captures and promotions
+    pub fn generate(&mut self, material: &str) -> Result<(), String> {
+        if self.tables.contains_key(material) {
+            return Ok(());
+        }
+        let pieces = Table::parse_material(material)?;
+
+        // tables after a capture or a promotion
+        for i in 0..pieces.len() {
+            let mut children = Vec::new();
+            if pieces[i].abs() != 6 {
+                let mut captured = pieces.clone();
+                captured.remove(i);
+                children.push(captured);
+            }
+            if pieces[i].abs() == 1 {
+                for promotion in 2..=5 {
+                    let mut promoted = pieces.clone();
+                    promoted[i] = promotion * pieces[i].signum();
+                    children.push(promoted);
+                }
+            }
+            for child in children {
+                let mut child: Pieces = child.into_iter().map(|piece| (piece, 0)).collect();
+                sort_pieces(&mut child);
+                if !is_dead_draw(&child) {
+                    self.generate(&material_name(&child))?;
+                }
+            }
+        }
+
+        let table = self.generate_table(material, pieces);
+        self.tables.insert(material.to_string(), table);
+        Ok(())
+    }
+
+    fn generate_table(&self, material: &str, pieces: Vec<i8>) -> Table {
+        let size = Table::size(pieces.len());
+        let mut table = Table {
+            material: material.to_string(),
+            pieces,
+            values: vec![0; size],
+        };
+        let mut state = vec![0u8; size];
+        // moves staying in the table not known to lose yet
+        let mut counters = vec![0u8; size];
+        // the longest loss found so far
+        let mut longest = vec![0u8; size];
+        // positions with their value known to be reached at the ply of their bucket
+        let mut buckets: Vec<Vec<(u32, Probe)>> = vec![Vec::new(); 256];
+
+        let mut data = empty_data();
+        let mut move_generator = MoveGenerator::new();
+
+        for index in 0..size {
+            let (squares, white_turn) = table.squares(index);
+            if !self.place(&mut data, &table.pieces, &squares, white_turn) {
+                self.clear(&mut data, &squares);
+                continue;
+            }
+            state[index] = VALID;
+            move_generator.generate_moves(&data);
+
+            if move_generator.moves.is_empty() {
+                if move_generator.in_check {
+                    buckets[0].push((index as u32, Probe::Loss(0)));
+                } else {
+                    state[index] |= RESOLVED;
+                }
+                self.clear(&mut data, &squares);
+                continue;
+            }
+
+            for _move in &move_generator.moves {
+                let mover = squares.iter().position(|s| *s == _move.start).unwrap();
+                let captured = squares.iter().position(|s| *s == _move.end);
+                if captured.is_none() && _move.promotion() == 0 {
+                    // en passant after a double step is not part of the index and not considered
+                    counters[index] += 1;
+                    continue;
+                }
+
+                // the move leaves the table
+                let mut child: Pieces = table
+                    .pieces
+                    .iter()
+                    .zip(&squares)
+                    .enumerate()
+                    .filter(|(i, _)| Some(*i) != captured)
+                    .map(|(i, (piece, square))| {
+                        if i == mover {
+                            let piece = match _move.promotion() {
+                                0 => *piece,
+                                promotion => (promotion as i8 + 1) * piece.signum(),
+                            };
+                            (piece, _move.end)
+                        } else {
+                            (*piece, *square)
+                        }
+                    })
+                    .collect();
+                sort_pieces(&mut child);
+                match self
+                    .probe_pieces(&child, !white_turn)
+                    .expect("tables of captures and promotions are generated first")
+                    .parent()
+                {
+                    Probe::Win(plies) => {
+                        state[index] |= NOT_LOST;
+                        buckets[plies as usize].push((index as u32, Probe::Win(plies)));
+                    }
+                    Probe::Draw => state[index] |= NOT_LOST,
+                    Probe::Loss(plies) => longest[index] = longest[index].max(plies),
+                }
+            }
+            if counters[index] == 0 && state[index] & NOT_LOST == 0 {
+                let plies = longest[index];
+                buckets[plies as usize].push((index as u32, Probe::Loss(plies)));
+            }
+            self.clear(&mut data, &squares);
+        }
+
+        // retrograde analysis, going back one ply at a time from the positions with known values
+        for ply in 0..buckets.len() {
+            let bucket = std::mem::take(&mut buckets[ply]);
+            for (index, value) in bucket {
+                let index = index as usize;
+                if state[index] & RESOLVED != 0 {
+                    continue;
+                }
+                state[index] |= RESOLVED;
+                table.values[index] = value.to_byte();
+
+                let (squares, white_turn) = table.squares(index);
+                for parent in table.unmoves(&squares, white_turn) {
+                    if state[parent] & (VALID | RESOLVED) != VALID {
+                        continue;
+                    }
+                    match value.parent() {
+                        Probe::Win(plies) => {
+                            if (plies as usize) < buckets.len() {
+                                buckets[plies as usize].push((parent as u32, Probe::Win(plies)));
+                            }
+                        }
+                        Probe::Loss(plies) => {
+                            counters[parent] -= 1;
+                            longest[parent] = longest[parent].max(plies);
+                            let plies = longest[parent] as usize;
+                            if counters[parent] == 0
+                                && state[parent] & NOT_LOST == 0
+                                && plies < buckets.len()
+                            {
+                                buckets[plies].push((parent as u32, Probe::Loss(plies as u8)));
+                            }
+                        }
+                        Probe::Draw => {}
+                    }
+                }
+            }
+        }
+        table
+    }
+
+    /// the legal positions of a generated table as the pieces with their squares, the side to
+    /// move and the value
+    #[cfg(test)]
+    pub(crate) fn positions<'a>(
+        &'a self,
+        material: &str,
+    ) -> impl Iterator<Item = (Vec<(i8, u8)>, bool, Probe)> + 'a {
+        let table = &self.tables[material];
+        let mut data = empty_data();
+        (0..table.values.len()).filter_map(move |index| {
+            let (squares, white_turn) = table.squares(index);
+            let legal = self.place(&mut data, &table.pieces, &squares, white_turn);
+            self.clear(&mut data, &squares);
+            let pieces = table.pieces.iter().copied().zip(squares).collect();
+            legal.then(|| (pieces, white_turn, Probe::from_byte(table.values[index])))
+        })
+    }
+
+    /// puts the pieces on the board, returns false if the position is not legal
+    fn place(&self, data: &mut Data, pieces: &[i8], squares: &[u8], white_turn: bool) -> bool {
+        for (i, (piece, square)) in pieces.iter().zip(squares).enumerate() {
+            let target = &mut data.board[*square as usize];
+            let pawn_on_back_rank = piece.abs() == 1 && (square / 8 == 0 || square / 8 == 7);
+            if !target.is_empty() || pawn_on_back_rank {
+                return false;
+            }
+            let color = if *piece > 0 {
+                PieceColor::White
+            } else {
+                PieceColor::Black
+            };
+            target.set(PieceType::from_piece(*piece), color, i as u8);
+        }
+        data.white_turn = white_turn;
+        data.piece_list.populate(&data.board);
+
+        // the side which just moved can not be in check
+        let color = if white_turn { 1 } else { -1 };
+        let king = pieces
+            .iter()
+            .position(|piece| *piece == -6 * color)
+            .unwrap();
+        MoveGenerator::attackers_of(data, squares[king], color) == 0
+    }
+
+    fn clear(&self, data: &mut Data, squares: &[u8]) {
+        for square in squares {
+            data.board[*square as usize].set_empty();
+        }
+    }
+}
+
+/// a board without pieces or castling rights
+fn empty_data() -> Data {
+    let mut data = Board::new().data;
+    for piece in &mut data.board {
+        piece.set_empty();
+    }
+    data.not_able_to_castle = 0x0f;
+    data.two_square_advance = 0;
+    data
+}
+
+impl Table {
+    /// indices of the positions from which a quiet move of the side which just moved leads to
+    /// the position, pieces move the same way backwards except for pawns
+    fn unmoves(&self, squares: &[u8], white_turn: bool) -> Vec<usize> {
+        let occupied = |square: i8| squares.iter().any(|s| *s as i8 == square);
+        let mut parents = Vec::new();
+        let mut add = |i: usize, square: i8| {
+            let parent = squares
+                .iter()
+                .enumerate()
+                .map(|(j, s)| if i == j { square as u8 } else { *s });
+            parents.push(Table::index(parent, !white_turn));
+        };
+
+        for (i, (piece, square)) in self.pieces.iter().zip(squares).enumerate() {
+            if (*piece > 0) == white_turn {
+                continue;
+            }
+            let (rank, file) = ((square / 8) as i8, (square % 8) as i8);
+            let on_board = |rank: i8, file: i8| (0..8).contains(&rank) && (0..8).contains(&file);
+
+            let (directions, slides): (&[(i8, i8)], bool) = match piece.abs() {
+                1 => {
+                    // pawns move back towards their own side
+                    let back = -piece.signum();
+                    let start_rank = if *piece > 0 { 1 } else { 6 };
+                    let from = rank + back;
+                    if from != 0 && from != 7 && !occupied(from * 8 + file) {
+                        add(i, from * 8 + file);
+                        if from + back == start_rank && !occupied((from + back) * 8 + file) {
+                            add(i, (from + back) * 8 + file);
+                        }
+                    }
+                    continue;
+                }
+                2 => (&DIAGONALS, true),
+                3 => (&KNIGHT, false),
+                4 => (&ORTHOGONALS, true),
+                _ => (&ALL_DIRECTIONS, piece.abs() == 5),
+            };
+            for (d_rank, d_file) in directions {
+                let (mut r, mut f) = (rank + d_rank, file + d_file);
+                while on_board(r, f) && !occupied(r * 8 + f) {
+                    add(i, r * 8 + f);
+                    if !slides {
+                        break;
+                    }
+                    r += d_rank;
+                    f += d_file;
+                }
+            }
+        }
+        parents
+    }
+}
+
+const DIAGONALS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+const ORTHOGONALS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const ALL_DIRECTIONS: [(i8, i8); 8] = [
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+];
+const KNIGHT: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (-1, 2),
+    (-2, 1),
+    (1, -2),
+    (2, -1),
+    (-1, -2),
+    (-2, -1),
+];
@@ -0,0 +1,112 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::Move;
+
+/// how the stored score relates to the value of the position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Bound {
+    Exact,
+    /// the value is at least the score, the search failed high
+    Lower,
+    /// the value is at most the score, the search failed low
+    Upper,
+}
+
+/// a search result of a position
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Entry {
+    pub(crate) depth: i32,
+    pub(crate) score: i32,
+    pub(crate) bound: Bound,
+    pub(crate) best_move: Option<Move>,
+}
+
+impl Entry {
+    // bits 0-5 start, 6-11 end, 12-19 flags, 20 has move, 21-22 bound, 24-31 depth, 32-63 score
+    fn pack(&self) -> u64 {
+        let mut data = match &self.best_move {
+            Some(_move) => {
+                _move.start as u64 | (_move.end as u64) << 6 | (_move.flags as u64) << 12 | 1 << 20
+            }
+            None => 0,
+        };
+        data |= (self.bound as u64) << 21;
+        data |= (self.depth.clamp(0, 255) as u64) << 24;
+        data | (self.score as u32 as u64) << 32
+    }
+
+    fn unpack(data: u64) -> Entry {
+        let best_move = (data & 1 << 20 != 0).then_some(Move {
+            start: (data & 0x3f) as u8,
+            end: (data >> 6 & 0x3f) as u8,
+            flags: (data >> 12) as u8,
+        });
+        let bound = match data >> 21 & 3 {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        };
+        Entry {
+            depth: (data >> 24 & 0xff) as i32,
+            score: (data >> 32) as u32 as i32,
+            bound,
+            best_move,
+        }
+    }
+}
+
+/// a hash table of search results shared between the threads of a search without locking.
+/// The key is stored xor the data, so an entry torn by concurrent writes does not match
+pub struct TranspositionTable {
+    /// pairs of the key xor the data and the data
+    entries: Vec<[AtomicU64; 2]>,
+}
+
+impl TranspositionTable {
+    /// a table taking about `megabytes` of memory
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        let size = (megabytes.max(1) << 20) / std::mem::size_of::<[AtomicU64; 2]>();
+        TranspositionTable {
+            entries: (0..size).map(|_| Default::default()).collect(),
+        }
+    }
+
+    pub fn clear(&self) {
+        for [key, data] in &self.entries {
+            key.store(0, Ordering::Relaxed);
+            data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// permille of the entries in use, estimated from the first thousand
+    pub fn hashfull(&self) -> u32 {
+        let sample = &self.entries[..self.entries.len().min(1000)];
+        let used = sample
+            .iter()
+            .filter(|[key, data]| key.load(Ordering::Relaxed) != data.load(Ordering::Relaxed))
+            .count();
+        (used * 1000 / sample.len()) as u32
+    }
+
+    fn slot(&self, hash: u64) -> &[AtomicU64; 2] {
+        &self.entries[(hash % self.entries.len() as u64) as usize]
+    }
+
+    pub(crate) fn probe(&self, hash: u64) -> Option<Entry> {
+        let [key, data] = self.slot(hash);
+        let data = data.load(Ordering::Relaxed);
+        (key.load(Ordering::Relaxed) ^ data == hash).then(|| Entry::unpack(data))
+    }
+
+    /// stores `entry` unless the slot holds a deeper result of the same position
+    pub(crate) fn store(&self, hash: u64, entry: Entry) {
+        let [key, data] = self.slot(hash);
+        let old = data.load(Ordering::Relaxed);
+        if key.load(Ordering::Relaxed) ^ old == hash && Entry::unpack(old).depth > entry.depth {
+            return;
+        }
+        let data_bits = entry.pack();
+        key.store(hash ^ data_bits, Ordering::Relaxed);
+        data.store(data_bits, Ordering::Relaxed);
+    }
+}
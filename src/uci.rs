@@ -0,0 +1,468 @@
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ScopedJoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::bot::{SearchInfo, SearchOptions};
+use crate::syzygy::Syzygy;
+use crate::tablebase::Tablebases;
+use crate::transposition::TranspositionTable;
+use crate::{bot, clock, Board, Book, Strength};
+
+const DEFAULT_DEPTH: i32 = 5;
+
+/// engine side of the UCI protocol, reads commands from `input` and answers on `output`
+pub struct UciEngine {
+    board: Board,
+    depth: i32,
+    chess960: bool,
+    book: Option<Book>,
+    book_depth: Option<usize>,
+    tablebases: Option<Arc<Tablebases>>,
+    syzygy: Option<Arc<Syzygy>>,
+    multi_pv: usize,
+    threads: usize,
+    limit_strength: bool,
+    elo: u32,
+    /// kept between the searches of a game
+    table: Arc<TranspositionTable>,
+}
+
+impl UciEngine {
+    pub fn new() -> UciEngine {
+        UciEngine {
+            board: Board::new(),
+            depth: DEFAULT_DEPTH,
+            chess960: false,
+            book: None,
+            book_depth: None,
+            tablebases: None,
+            syzygy: None,
+            multi_pv: 1,
+            threads: 1,
+            limit_strength: false,
+            elo: Strength::MAX_ELO,
+            table: Arc::new(TranspositionTable::new(bot::DEFAULT_HASH)),
+        }
+    }
+
+    /// handles commands until `quit` is received or `input` is closed. Searches run on another
+    /// thread, commands changing the position or the options stop them like `stop`
+    pub fn run<R: BufRead, W: Write + Send>(&mut self, input: R, output: W) -> io::Result<()> {
+        let output = Mutex::new(output);
+        thread::scope(|scope| {
+            let mut search: Option<Search> = None;
+            for line in input.lines() {
+                let line = line?;
+                let mut tokens = line.split_whitespace();
+                let command = tokens.next();
+                match command {
+                    Some("uci") | Some("isready") => {}
+                    Some("ponderhit") => search.iter().for_each(Search::ponderhit),
+                    Some("stop") | Some("quit") | Some("ucinewgame") | Some("setoption")
+                    | Some("position") | Some("go") => finish(search.take(), true)?,
+                    _ => {}
+                }
+
+                let mut out = output.lock().unwrap();
+                match command {
+                    Some("uci") => {
+                        writeln!(out, "id name chess {}", env!("CARGO_PKG_VERSION"))?;
+                        writeln!(out, "id author chess contributors")?;
+                        writeln!(
+                            out,
+                            "option name Depth type spin default {} min 1 max 20",
+                            DEFAULT_DEPTH
+                        )?;
+                        writeln!(out, "option name UCI_Chess960 type check default false")?;
+                        writeln!(out, "option name BookFile type string default <empty>")?;
+                        writeln!(
+                            out,
+                            "option name BookDepth type spin default 0 min 0 max 500"
+                        )?;
+                        writeln!(out, "option name TablebasePath type string default <empty>")?;
+                        writeln!(out, "option name SyzygyPath type string default <empty>")?;
+                        writeln!(out, "option name MultiPV type spin default 1 min 1 max 500")?;
+                        writeln!(
+                            out,
+                            "option name Hash type spin default {} min 1 max 4096",
+                            bot::DEFAULT_HASH
+                        )?;
+                        writeln!(out, "option name Threads type spin default 1 min 1 max 256")?;
+                        writeln!(out, "option name Ponder type check default false")?;
+                        writeln!(
+                            out,
+                            "option name UCI_LimitStrength type check default false"
+                        )?;
+                        writeln!(
+                            out,
+                            "option name UCI_Elo type spin default {max} min {} max {max}",
+                            Strength::MIN_ELO,
+                            max = Strength::MAX_ELO
+                        )?;
+                        writeln!(out, "uciok")?;
+                    }
+                    Some("isready") => writeln!(out, "readyok")?,
+                    Some("ucinewgame") => {
+                        self.board = Board::new();
+                        self.table.clear();
+                    }
+                    Some("setoption") => {
+                        if let Err(message) = self.set_option(&line) {
+                            writeln!(out, "info string {}", message)?;
+                        }
+                    }
+                    Some("position") => {
+                        if let Err(message) = self.position(tokens) {
+                            writeln!(out, "info string {}", message)?;
+                        }
+                    }
+                    Some("go") => {
+                        if let Some((board, mut options, infinite)) = self.go(tokens, &mut *out)? {
+                            let stop = Arc::new(AtomicBool::new(false));
+                            options.stop = Some(stop.clone());
+                            let output = &output;
+                            let strength = self.strength();
+                            search = Some(Search {
+                                stop,
+                                infinite,
+                                pondering: options.pondering.clone(),
+                                budget: options.budget,
+                                start: Instant::now(),
+                                thread: scope.spawn(move || {
+                                    search_and_report(board, options, strength, output)
+                                }),
+                            });
+                        }
+                    }
+                    Some("quit") => break,
+                    _ => {}
+                }
+                out.flush()?;
+            }
+            // without further input an infinite or pondering search would never end
+            let infinite = search.as_ref().is_some_and(Search::endless);
+            finish(search, infinite)
+        })
+    }
+
+    fn set_option(&mut self, line: &str) -> Result<(), String> {
+        // setoption name <id> [value <x>], the name may contain spaces
+        let line = line.trim_start_matches("setoption").trim();
+        let line = line.strip_prefix("name").unwrap_or(line);
+        let (name, value) = match line.find(" value ") {
+            Some(i) => (line[..i].trim(), line[i + 7..].trim()),
+            None => (line.trim(), ""),
+        };
+
+        match name.to_ascii_lowercase().as_str() {
+            "depth" => {
+                if let Ok(depth) = value.parse::<i32>() {
+                    self.depth = depth.clamp(1, 20);
+                }
+            }
+            "uci_chess960" => {
+                self.chess960 = value == "true";
+                self.board.set_chess960(self.chess960);
+            }
+            "bookfile" => {
+                self.book = match value {
+                    "" | "<empty>" => None,
+                    path => Some(
+                        Book::open(path)
+                            .map_err(|err| format!("unable to read book {}: {}", path, err))?,
+                    ),
+                };
+                self.update_book_depth();
+            }
+            "bookdepth" => {
+                // 0 plays from the book as long as possible
+                self.book_depth = value.parse().ok().filter(|depth| *depth > 0);
+                self.update_book_depth();
+            }
+            "tablebasepath" => {
+                self.tablebases = match value {
+                    "" | "<empty>" => None,
+                    path => Some(Arc::new(Tablebases::load(path).map_err(|err| {
+                        format!("unable to read tablebases {}: {}", path, err)
+                    })?)),
+                };
+            }
+            "multipv" => {
+                if let Ok(multi_pv) = value.parse::<usize>() {
+                    self.multi_pv = multi_pv.clamp(1, 500);
+                }
+            }
+            "hash" => {
+                if let Ok(megabytes) = value.parse::<usize>() {
+                    self.table = Arc::new(TranspositionTable::new(megabytes.clamp(1, 4096)));
+                }
+            }
+            "uci_limitstrength" => self.limit_strength = value == "true",
+            "uci_elo" => {
+                if let Ok(elo) = value.parse::<u32>() {
+                    self.elo = elo.clamp(Strength::MIN_ELO, Strength::MAX_ELO);
+                }
+            }
+            "threads" => {
+                if let Ok(threads) = value.parse::<usize>() {
+                    self.threads = threads.clamp(1, 256);
+                }
+            }
+            "syzygypath" => {
+                self.syzygy = match value {
+                    "" | "<empty>" => None,
+                    paths => Some(Arc::new(Syzygy::load(paths).map_err(|err| {
+                        format!("unable to read Syzygy tables {}: {}", paths, err)
+                    })?)),
+                };
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// the strength to play at if it is limited
+    fn strength(&self) -> Option<Strength> {
+        self.limit_strength.then(|| Strength::from_elo(self.elo))
+    }
+
+    fn update_book_depth(&mut self) {
+        if let Some(book) = &mut self.book {
+            book.set_max_ply(self.book_depth);
+        }
+    }
+
+    fn position<'a>(&mut self, mut tokens: impl Iterator<Item = &'a str>) -> Result<(), String> {
+        let mut board = match tokens.next() {
+            Some("startpos") => {
+                tokens.next(); // moves
+                Board::new()
+            }
+            Some("fen") => {
+                let fen: Vec<&str> = tokens.by_ref().take_while(|t| *t != "moves").collect();
+                Board::try_from_fen(&fen.join(" "))
+                    .map_err(|err| format!("invalid position: {}", err))?
+            }
+            _ => return Err(String::from("expected startpos or fen")),
+        };
+        if self.chess960 {
+            board.set_chess960(true);
+        }
+
+        for uci in tokens {
+            let _move = board
+                .move_from_uci(uci)
+                .ok_or_else(|| format!("illegal move {}", uci))?;
+            board.push(_move).map_err(|err| err.to_string())?;
+        }
+
+        self.board = board;
+        Ok(())
+    }
+
+    /// the position and options to search, and whether the search is infinite, `None` if the
+    /// move was answered from the book. `go ponder` searches the position after the expected
+    /// reply until `ponderhit` or `stop`
+    fn go<'a, W: Write>(
+        &mut self,
+        mut tokens: impl Iterator<Item = &'a str>,
+        output: &mut W,
+    ) -> io::Result<Option<(Board, SearchOptions, bool)>> {
+        let mut depth = None;
+        let mut infinite = false;
+        let mut ponder = false;
+        let mut nodes = None;
+        let mut movetime = None;
+        let mut time = [None, None];
+        let mut increment = [Duration::ZERO; 2];
+        let mut moves_to_go = None;
+        while let Some(token) = tokens.next() {
+            let value = |tokens: &mut dyn Iterator<Item = &'a str>| {
+                tokens.next().and_then(|value| value.parse::<u64>().ok())
+            };
+            match token {
+                "depth" => depth = value(&mut tokens).map(|d| d as i32),
+                "movetime" => movetime = value(&mut tokens).map(Duration::from_millis),
+                "wtime" => time[0] = value(&mut tokens).map(Duration::from_millis),
+                "btime" => time[1] = value(&mut tokens).map(Duration::from_millis),
+                "winc" => {
+                    increment[0] = value(&mut tokens).map_or(Duration::ZERO, Duration::from_millis)
+                }
+                "binc" => {
+                    increment[1] = value(&mut tokens).map_or(Duration::ZERO, Duration::from_millis)
+                }
+                "movestogo" => moves_to_go = value(&mut tokens).map(|m| m as u32),
+                "infinite" => infinite = true,
+                "ponder" => ponder = true,
+                "nodes" => nodes = value(&mut tokens),
+                _ => {}
+            }
+        }
+
+        let side = !self.board.data.white_turn as usize;
+        let budget = match (depth, movetime, time[side]) {
+            _ if infinite => None,
+            (Some(_), _, _) => None,
+            (None, Some(movetime), _) => Some(movetime),
+            (None, None, Some(time)) => {
+                Some(clock::move_budget(time, increment[side], moves_to_go))
+            }
+            _ => None,
+        };
+
+        // a book move would be sent before `ponderhit`, pondering searches instead
+        let book_move = self
+            .book
+            .as_ref()
+            .filter(|_| !ponder)
+            .and_then(|book| book.choose_move(&self.board, &mut rand::thread_rng()));
+        if let Some(_move) = book_move {
+            writeln!(output, "bestmove {}", self.board.move_to_uci(&_move))?;
+            return Ok(None);
+        }
+
+        let mut options = SearchOptions {
+            max_depth: if infinite {
+                bot::MAX_DEPTH
+            } else {
+                depth.unwrap_or(self.depth)
+            },
+            budget,
+            lines: self.multi_pv,
+            tablebases: self.tablebases.clone(),
+            syzygy: self.syzygy.clone(),
+            stop: None,
+            threads: self.threads,
+            table: Some(self.table.clone()),
+            pondering: ponder.then(|| Arc::new(AtomicBool::new(true))),
+            nodes,
+        };
+        if let Some(strength) = self.strength() {
+            if let Some(depth) = strength.depth() {
+                options.max_depth = options.max_depth.min(depth);
+            }
+            options.lines = options.lines.max(strength.lines());
+            options.nodes = options.nodes.into_iter().chain(strength.nodes()).min();
+        }
+        Ok(Some((self.board.clone(), options, infinite)))
+    }
+}
+
+/// a search started by `go`, writing its best move when it finished
+struct Search<'scope> {
+    stop: Arc<AtomicBool>,
+    /// `go infinite` searches until it is stopped
+    infinite: bool,
+    /// set until `ponderhit` for `go ponder`
+    pondering: Option<Arc<AtomicBool>>,
+    budget: Option<Duration>,
+    start: Instant,
+    thread: ScopedJoinHandle<'scope, io::Result<()>>,
+}
+
+impl Search<'_> {
+    /// whether the search only ends when stopped
+    fn endless(&self) -> bool {
+        self.infinite || is_set(&self.pondering)
+    }
+
+    /// continues a ponder search with the time since `go ponder` used from its budget
+    fn ponderhit(&self) {
+        if let Some(pondering) = &self.pondering {
+            pondering.store(false, Ordering::Relaxed);
+        }
+        if self
+            .budget
+            .is_some_and(|budget| self.start.elapsed() >= budget)
+        {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+fn is_set(flag: &Option<Arc<AtomicBool>>) -> bool {
+    flag.as_ref()
+        .is_some_and(|flag| flag.load(Ordering::Relaxed))
+}
+
+/// waits for the search to write its best move, `stop` ends it early
+fn finish(search: Option<Search>, stop: bool) -> io::Result<()> {
+    match search {
+        Some(search) => {
+            if stop {
+                search.stop.store(true, Ordering::Relaxed);
+            }
+            search.thread.join().expect("search thread panicked")
+        }
+        None => Ok(()),
+    }
+}
+
+/// a score in centipawns as sent in an info line, mates as the moves until mate
+fn uci_score(score: i32) -> String {
+    match bot::mate_in(score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", score),
+    }
+}
+
+/// searches and writes the progress and the best move, a limited `strength` chooses the move
+fn search_and_report<W: Write>(
+    mut board: Board,
+    options: SearchOptions,
+    strength: Option<Strength>,
+    output: &Mutex<W>,
+) -> io::Result<()> {
+    let position = board.clone();
+    let mut written = Ok(());
+    let lines = bot::search_observed(&mut board, &options, &mut |info: &SearchInfo| {
+        if written.is_ok() {
+            let mut output = output.lock().unwrap();
+            written = writeln!(
+                output,
+                "info depth {} seldepth {} multipv {} score {} nodes {} nps {} \
+                hashfull {} time {} pv {}",
+                info.depth,
+                info.seldepth,
+                info.multipv,
+                uci_score(info.line.score),
+                info.nodes,
+                info.nps,
+                info.hashfull,
+                info.time.as_millis(),
+                info.line.to_uci(&position)
+            )
+            .and_then(|_| output.flush());
+        }
+    });
+    written?;
+    // the move found while pondering is only sent after `ponderhit` or `stop`
+    while is_set(&options.pondering) && !is_set(&options.stop) {
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    let mut output = output.lock().unwrap();
+    let line = match strength {
+        Some(strength) => strength.choose(&lines, &mut rand::thread_rng()),
+        None => lines.first(),
+    };
+    match line.map(|line| &line.moves[..]) {
+        Some([best, reply, ..]) => {
+            let mut board = position.clone();
+            board.make_move(best);
+            let (best, reply) = (position.move_to_uci(best), board.move_to_uci(reply));
+            writeln!(output, "bestmove {} ponder {}", best, reply)?
+        }
+        Some([best]) => writeln!(output, "bestmove {}", position.move_to_uci(best))?,
+        _ => writeln!(output, "bestmove 0000")?,
+    }
+    output.flush()
+}
+
+impl Default for UciEngine {
+    fn default() -> Self {
+        UciEngine::new()
+    }
+}
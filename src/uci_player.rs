@@ -0,0 +1,274 @@
+use std::ffi::OsStr;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use crate::bot::MATE_SCORE;
+use crate::clock::Clock;
+use crate::player::{self, Player, PlayerAction};
+use crate::{Board, Strength};
+
+/// option announced by the engine during the handshake
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UciOption {
+    pub name: String,
+    pub kind: String,
+    pub default: Option<String>,
+}
+
+/// player backed by an external engine speaking UCI over stdin/stdout
+pub struct UciPlayer {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    name: String,
+    options: Vec<UciOption>,
+    go: Option<String>,
+    chess960: bool,
+    score: Option<i32>,
+    /// why the engine stopped working, it resigns every game from then on
+    failure: Option<String>,
+}
+
+impl UciPlayer {
+    /// starts the engine at `path` and waits for `uciok`
+    pub fn new<S: AsRef<OsStr>>(path: S) -> io::Result<UciPlayer> {
+        UciPlayer::with_args(path, std::iter::empty::<&OsStr>())
+    }
+
+    pub fn with_args<S, I, A>(path: S, args: I) -> io::Result<UciPlayer>
+    where
+        S: AsRef<OsStr>,
+        I: IntoIterator<Item = A>,
+        A: AsRef<OsStr>,
+    {
+        let mut child = Command::new(path.as_ref())
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+
+        let mut player = UciPlayer {
+            child,
+            stdin,
+            stdout,
+            name: path.as_ref().to_string_lossy().into_owned(),
+            options: Vec::new(),
+            go: None,
+            chess960: false,
+            score: None,
+            failure: None,
+        };
+
+        player.send("uci")?;
+        loop {
+            let line = player.read_line()?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                player.name = name.trim().to_string();
+            } else if let Some(option) = line.strip_prefix("option ") {
+                player.options.extend(parse_option(option));
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+        player.send("ucinewgame")?;
+        player.wait_ready()?;
+        Ok(player)
+    }
+
+    /// the error that made the engine resign, ex.: it exited or sent an illegal move
+    pub fn failure(&self) -> Option<&str> {
+        self.failure.as_deref()
+    }
+
+    pub fn options(&self) -> &[UciOption] {
+        &self.options
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        self.send(&format!("setoption name {} value {}", name, value))?;
+        self.wait_ready()
+    }
+
+    /// sets the search limits sent with every `go`, ex.: "depth 8" or "movetime 500", by default
+    /// the time left on the clock is sent
+    pub fn set_limits(&mut self, limits: &str) {
+        self.go = Some(format!("go {}", limits));
+    }
+
+    pub fn new_game(&mut self) -> io::Result<()> {
+        self.send("ucinewgame")?;
+        self.wait_ready()
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("engine {} closed its output", self.name),
+            ));
+        }
+        Ok(line)
+    }
+
+    fn wait_ready(&mut self) -> io::Result<()> {
+        self.send("isready")?;
+        while self.read_line()?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    fn best_move(&mut self, board: &Board, clock: &Clock) -> io::Result<String> {
+        if board.is_chess960() != self.chess960 {
+            self.chess960 = board.is_chess960();
+            self.set_option("UCI_Chess960", if self.chess960 { "true" } else { "false" })?;
+        }
+
+        let moves: Vec<String> = board
+            .record()
+            .moves()
+            .map(|_move| board.move_to_uci(_move))
+            .collect();
+        let mut position = format!("position fen {}", board.record().start_fen());
+        if !moves.is_empty() {
+            position.push_str(" moves ");
+            position.push_str(&moves.join(" "));
+        }
+        self.send(&position)?;
+        let go = match (&self.go, clock.remaining(true), clock.remaining(false)) {
+            (Some(go), _, _) => go.clone(),
+            (None, Some(wtime), Some(btime)) => {
+                let mut go = format!(
+                    "go wtime {} btime {} winc {} binc {}",
+                    wtime.as_millis(),
+                    btime.as_millis(),
+                    clock.increment(true).as_millis(),
+                    clock.increment(false).as_millis()
+                );
+                if let Some(moves_to_go) = clock.moves_to_go(board.data.white_turn) {
+                    go.push_str(&format!(" movestogo {}", moves_to_go));
+                }
+                go
+            }
+            (None, _, _) => String::from("go movetime 1000"),
+        };
+        self.send(&go)?;
+
+        self.score = None;
+        loop {
+            let line = self.read_line()?;
+            if line.starts_with("info") {
+                self.score = parse_score(&line).or(self.score);
+            } else if let Some(best_move) = line.strip_prefix("bestmove") {
+                return Ok(best_move
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string());
+            }
+        }
+    }
+}
+
+/// score of an info line in centipawns, a mate in `n` plies is mapped to `bot::MATE_SCORE - n`
+fn parse_score(info: &str) -> Option<i32> {
+    let mut tokens = info
+        .split_whitespace()
+        .skip_while(|t| *t != "score")
+        .skip(1);
+    let kind = tokens.next()?;
+    let value: i32 = tokens.next()?.parse().ok()?;
+    match kind {
+        "cp" => Some(value),
+        "mate" if value > 0 => Some(MATE_SCORE - (2 * value - 1)),
+        "mate" => Some(-(MATE_SCORE + 2 * value)),
+        _ => None,
+    }
+}
+
+fn parse_option(option: &str) -> Option<UciOption> {
+    // name <id> type <t> [default <x>] [min <x>] [max <x>] [var <x>]*
+    let option = option.trim().strip_prefix("name ")?;
+    let type_start = option.find(" type ")?;
+    let name = option[..type_start].trim().to_string();
+    let mut rest = option[type_start + 6..].split_whitespace();
+    let kind = rest.next()?.to_string();
+    let default = match rest.next() {
+        Some("default") => Some(
+            rest.take_while(|t| !["min", "max", "var"].contains(t))
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        _ => None,
+    };
+    Some(UciOption {
+        name,
+        kind,
+        default,
+    })
+}
+
+impl Player for UciPlayer {
+    /// resigns if the engine fails or sends an illegal move
+    fn choose_move(&mut self, board: &Board, clock: &Clock) -> PlayerAction {
+        if self.failure.is_some() {
+            return PlayerAction::Resign;
+        }
+        match self.best_move(board, clock) {
+            Ok(best_move) => match board.move_from_uci(&best_move) {
+                Some(_move) => return PlayerAction::Move(_move),
+                None => {
+                    self.failure = Some(format!(
+                        "engine {} sent illegal move {}",
+                        self.name, best_move
+                    ))
+                }
+            },
+            Err(err) => self.failure = Some(format!("engine {} failed: {}", self.name, err)),
+        }
+        PlayerAction::Resign
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn score(&self) -> Option<i32> {
+        self.score
+    }
+
+    fn draw_offered(&mut self, _board: &Board) -> bool {
+        player::accepts_draw(self.score)
+    }
+
+    /// limits the strength by Elo if the engine supports it
+    fn set_strength(&mut self, strength: Strength) {
+        if !self
+            .options
+            .iter()
+            .any(|option| option.name == "UCI_LimitStrength")
+        {
+            return;
+        }
+        let result = self
+            .set_option("UCI_LimitStrength", "true")
+            .and_then(|_| self.set_option("UCI_Elo", &strength.elo().to_string()));
+        if let Err(err) = result {
+            self.failure = Some(format!("engine {} failed: {}", self.name, err));
+        }
+    }
+}
+
+impl Drop for UciPlayer {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{board::Data, Piece};
+
+/// random keys for zobrist hashing, generated at compile time with splitmix64
+pub(crate) struct Zobrist {
+    pieces: [[u64; 64]; 12],
+    castling: [u64; 16], // indexed by not_able_to_castle
+    en_passant: [u64; 8],
+    black_to_move: u64,
+}
+
+pub(crate) static ZOBRIST: Zobrist = Zobrist::new();
+
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    (state, z ^ (z >> 31))
+}
+
+impl Zobrist {
+    const fn new() -> Zobrist {
+        let mut zobrist = Zobrist {
+            pieces: [[0; 64]; 12],
+            castling: [0; 16],
+            en_passant: [0; 8],
+            black_to_move: 0,
+        };
+        let mut state = 0x5eed;
+        let mut key;
+
+        let mut i = 0;
+        while i < 12 * 64 {
+            (state, key) = splitmix64(state);
+            zobrist.pieces[i / 64][i % 64] = key;
+            i += 1;
+        }
+
+        // combination of the keys of each single castling right, so that changing one right
+        // only changes the corresponding part of the hash
+        let mut rights = [0; 4];
+        i = 0;
+        while i < 4 {
+            (state, rights[i]) = splitmix64(state);
+            i += 1;
+        }
+        i = 0;
+        while i < 16 {
+            let mut bit = 0;
+            while bit < 4 {
+                if i & (1 << bit) == 0 {
+                    zobrist.castling[i] ^= rights[bit];
+                }
+                bit += 1;
+            }
+            i += 1;
+        }
+
+        i = 0;
+        while i < 8 {
+            (state, key) = splitmix64(state);
+            zobrist.en_passant[i] = key;
+            i += 1;
+        }
+
+        (_, zobrist.black_to_move) = splitmix64(state);
+        zobrist
+    }
+
+    #[inline(always)]
+    pub(crate) fn piece(&self, piece: &Piece, index: u8) -> u64 {
+        self.pieces[piece.to_piecelist_index()][index as usize]
+    }
+
+    #[inline(always)]
+    pub(crate) fn castling(&self, not_able_to_castle: u8) -> u64 {
+        self.castling[not_able_to_castle as usize]
+    }
+
+    #[inline(always)]
+    pub(crate) fn en_passant(&self, two_square_advance: u8) -> u64 {
+        if two_square_advance == 0 {
+            0
+        } else {
+            self.en_passant[(two_square_advance % 8) as usize]
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn side(&self) -> u64 {
+        self.black_to_move
+    }
+
+    /// calculates the hash of a position from scratch
+    pub(crate) fn hash(&self, data: &Data) -> u64 {
+        let mut hash =
+            self.castling(data.not_able_to_castle) ^ self.en_passant(data.two_square_advance);
+        if !data.white_turn {
+            hash ^= self.side();
+        }
+        for (index, piece) in data.board.iter().enumerate() {
+            if !piece.is_empty() {
+                hash ^= self.piece(piece, index as u8);
+            }
+        }
+        hash
+    }
+}
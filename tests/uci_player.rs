@@ -0,0 +1,94 @@
+use chess::{
+    BackgroundPlayer, Board, Clock, Game, GameResult, Player, PlayerAction, RandomPlayer, Reason,
+    UciPlayer,
+};
+
+fn engine() -> UciPlayer {
+    let mut engine = UciPlayer::new(env!("CARGO_BIN_EXE_uci")).unwrap();
+    engine.set_limits("depth 2");
+    engine
+}
+
+#[test]
+fn handshake() {
+    let engine = engine();
+    assert!(engine.name().starts_with("chess"));
+    assert!(engine
+        .options()
+        .iter()
+        .any(|option| option.name == "UCI_Chess960" && option.kind == "check"));
+}
+
+#[test]
+fn engine_finds_mate() {
+    let mut game = Game::from_fen(
+        "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+        Box::new(engine()),
+        Box::new(RandomPlayer::from_seed(0)),
+    );
+    game.step().unwrap();
+    assert_eq!(
+        game.result(),
+        Some(GameResult::win_for(true, Reason::Checkmate))
+    );
+}
+
+#[test]
+fn engine_against_random_player() {
+    let mut game = Game::new(Box::new(RandomPlayer::from_seed(4)), Box::new(engine()));
+    for _ in 0..20 {
+        if game.step().unwrap().is_none() {
+            break;
+        }
+    }
+    assert!(game.board().ply() >= 20 || game.is_over());
+}
+
+#[test]
+fn engine_in_background() {
+    let mut game = Game::from_fen(
+        "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+        Box::new(BackgroundPlayer::new(Box::new(engine()))),
+        Box::new(RandomPlayer::from_seed(0)),
+    );
+    // the engine thinks on another thread while the game waits
+    assert_eq!(game.step(), Ok(None));
+    assert!(game.player(true).thinking().is_some());
+    while let Ok(None) = game.step() {
+        assert!(!game.is_over());
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert_eq!(
+        game.result(),
+        Some(GameResult::win_for(true, Reason::Checkmate))
+    );
+}
+
+/// an engine completing the handshake and then answering `go` with `on_go`
+fn broken_engine(on_go: &str) -> UciPlayer {
+    let script = format!(
+        "while read line; do case $line in uci) echo uciok;; isready) echo readyok;; \
+        quit) exit;; go*) {};; esac; done",
+        on_go
+    );
+    UciPlayer::with_args("sh", ["-c", &script]).unwrap()
+}
+
+#[test]
+fn broken_engines_resign() {
+    let mut engine = broken_engine("echo bestmove e2e5");
+    let (board, clock) = (Board::new(), Clock::unlimited());
+    assert_eq!(engine.choose_move(&board, &clock), PlayerAction::Resign);
+    assert_eq!(engine.failure(), Some("engine sh sent illegal move e2e5"));
+
+    let mut game = Game::new(
+        Box::new(RandomPlayer::from_seed(0)),
+        Box::new(broken_engine("exit")),
+    );
+    game.step().unwrap();
+    assert_eq!(game.step(), Ok(None));
+    assert_eq!(
+        game.result(),
+        Some(GameResult::win_for(true, Reason::Resignation))
+    );
+}